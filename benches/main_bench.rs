@@ -1,10 +1,84 @@
-use quatro_in_rust::themain;
 use criterion::{criterion_group, criterion_main, Criterion};
+use quatro_in_rust::bitboard::BitGame;
+use quatro_in_rust::random::SplitMix64;
+use quatro_in_rust::{Coordinate, Game, GameResult, QuartoAction, QuartoMinimax};
 
+use std::collections::HashMap;
+
+// Values of every action of a position with 7 pieces placed, from an empty memory
+fn evaluate_all_actions() -> Vec<(QuartoAction, i32)> {
+    let mut game = Game::new();
+
+    let pieces_with_coordinates = vec![
+        // ([false, false, false, false], (0, 0)),
+        ([false, false, false, true], (0, 1)),
+        ([false, false, true, false], (0, 2)),
+        ([false, true, false, false], (1, 0)),
+        ([false, true, false, true], (1, 1)),
+        ([false, true, true, false], (1, 2)),
+        ([true, true, false, false], (2, 0)),
+        ([true, true, false, true], (2, 1)),
+        // ([true, true, true, false], (2, 2)),
+    ];
+
+    for (piece, (row, column)) in pieces_with_coordinates {
+        game.choose(piece).unwrap();
+        game.put(Coordinate { row, column }).unwrap();
+    }
+
+    let mut qmm = QuartoMinimax::new(HashMap::new());
+    qmm.evaluate_all_actions(&game)
+}
 
 fn main_benchmark(c: &mut Criterion) {
-    c.bench_function("themain", |b| b.iter(|| themain()));
+    c.bench_function("evaluate all actions", |b| b.iter(evaluate_all_actions));
+}
+
+// Deterministic games so both representations replay exactly the same actions
+fn random_games(count: usize) -> Vec<Vec<QuartoAction>> {
+    let mut random = SplitMix64::new(0x2545_f491_4f6c_dd1d);
+
+    (0..count)
+        .map(|_| {
+            let mut game = Game::new();
+            let mut actions = vec![];
+            while *game.result() == GameResult::InProgress {
+                // Legal actions come in a fixed order, unlike the pieces left
+                let legal_actions = game.legal_actions();
+                let action = legal_actions[random.below(legal_actions.len())];
+                game.play(action).unwrap();
+                actions.push(action);
+            }
+            actions
+        })
+        .collect()
+}
+
+fn representation_benchmark(c: &mut Criterion) {
+    let games = random_games(100);
+
+    c.bench_function("grid replay", |b| {
+        b.iter(|| {
+            for actions in &games {
+                let mut game = Game::new();
+                for action in actions {
+                    game.play(*action).unwrap();
+                }
+            }
+        })
+    });
+
+    c.bench_function("bitboard replay", |b| {
+        b.iter(|| {
+            for actions in &games {
+                let mut game = BitGame::new();
+                for action in actions {
+                    game.play(*action).unwrap();
+                }
+            }
+        })
+    });
 }
 
-criterion_group!(benches, main_benchmark);
+criterion_group!(benches, main_benchmark, representation_benchmark);
 criterion_main!(benches);
@@ -0,0 +1,116 @@
+//! Minimax with alpha-beta pruning: actions that can't change the value of a state are not
+//! searched.
+
+use crate::minimax::{Minimax, Side};
+
+/// Range of values the caller of a search cares about. Values at or below `alpha` are all equally
+/// bad for Max, and values at or above `beta` are all equally bad for Min, so the search can stop
+/// looking into them as soon as it knows a value falls outside the window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Window {
+    /// Values at or below it are all equally bad for Max.
+    pub alpha: i32,
+    /// Values at or above it are all equally bad for Min.
+    pub beta: i32,
+}
+
+impl Window {
+    /// The window that makes the search return the exact value.
+    pub const FULL: Window = Window {
+        alpha: i32::MIN,
+        beta: i32::MAX,
+    };
+
+    /// The window of the values strictly between `alpha` and `beta`.
+    pub fn new(alpha: i32, beta: i32) -> Window {
+        Window { alpha, beta }
+    }
+
+    /// Whether a value returned by a search with this window is exact rather than a bound.
+    pub fn contains(&self, value: i32) -> bool {
+        self.alpha < value && value < self.beta
+    }
+}
+
+/// Value of `state` searched with alpha-beta pruning. The result is exact when it falls strictly
+/// inside `window`. Otherwise it's a bound: if it's at or below `alpha` the exact value is at most
+/// the result, and if it's at or above `beta` the exact value is at least the result.
+pub fn alpha_beta<State, Action, M>(problem: &mut M, state: &State, window: Window) -> i32
+where
+    M: Minimax<State, Action>,
+{
+    if problem.terminal(state) {
+        return problem.utility(state);
+    }
+
+    problem.memoized_within(state, window, |problem| {
+        let Window {
+            mut alpha,
+            mut beta,
+        } = window;
+
+        let best_possible = problem.best_possible(state);
+        match problem.to_move(state) {
+            Side::Max => {
+                let mut v = i32::MIN;
+                for action in problem.actions(state) {
+                    let next_state = problem.result(state, action);
+                    v = v.max(alpha_beta(problem, &next_state, Window::new(alpha, beta)));
+                    if v >= beta || Some(v) == best_possible {
+                        break;
+                    }
+                    alpha = alpha.max(v);
+                }
+                v
+            }
+            Side::Min => {
+                let mut v = i32::MAX;
+                for action in problem.actions(state) {
+                    let next_state = problem.result(state, action);
+                    v = v.min(alpha_beta(problem, &next_state, Window::new(alpha, beta)));
+                    if v <= alpha || Some(v) == best_possible {
+                        break;
+                    }
+                    beta = beta.min(v);
+                }
+                v
+            }
+        }
+    })
+}
+
+/// The best of `actions` for the player to move in `state`, with its value. Each action is only
+/// searched for values better than the best one so far. Returns `None` if `actions` is empty.
+pub fn best_action_among<State, Action, M>(
+    problem: &mut M,
+    state: &State,
+    actions: Vec<Action>,
+) -> Option<(Action, i32)>
+where
+    Action: Copy,
+    M: Minimax<State, Action>,
+{
+    let side = problem.to_move(state);
+    let mut best: Option<(Action, i32)> = None;
+
+    for action in actions {
+        let next_state = problem.result(state, action);
+        let window = match (side, best) {
+            (_, None) => Window::FULL,
+            (Side::Max, Some((_, value))) => Window::new(value, i32::MAX),
+            (Side::Min, Some((_, value))) => Window::new(i32::MIN, value),
+        };
+        let value = alpha_beta(problem, &next_state, window);
+
+        let better = match (side, best) {
+            (_, None) => true,
+            (Side::Max, Some((_, best_value))) => value > best_value,
+            (Side::Min, Some((_, best_value))) => value < best_value,
+        };
+        if better {
+            best = Some((action, value));
+        }
+    }
+
+    best
+}
@@ -0,0 +1,406 @@
+//! Compact representation of a game using bit masks instead of a grid. It's what the solver
+//! searches and remembers, see [`QuartoMinimax`](crate::QuartoMinimax).
+//!
+//! Cells are numbered row by row, so the cell at `row` and `column` is the bit
+//! `row * BOARD_SIZE + column` of every mask. Pieces are numbered by packing their attributes as
+//! in the position notation.
+
+use crate::board::Board;
+use crate::coordinate::Coordinate;
+use crate::error::QuartoError;
+use crate::game::{Game, GameResult, GameState, Player, QuartoAction, Stage};
+use crate::piece::{piece_from_index, piece_to_index, Piece};
+use crate::symmetry;
+use crate::zobrist;
+use crate::{BOARD_SIZE, N_PROPERTIES};
+
+use std::hash::{Hash, Hasher};
+
+const N_PIECES: usize = 1 << N_PROPERTIES;
+const ALL_PIECES: u16 = u16::MAX;
+// A cell without a piece, in the arrays of pieces by cell
+const EMPTY: u8 = N_PIECES as u8;
+
+// Masks of the cells of every row, column and diagonal
+const LINES: [u16; 10] = [
+    0x000f, 0x00f0, 0x0f00, 0xf000, // rows
+    0x1111, 0x2222, 0x4444, 0x8888, // columns
+    0x8421, 0x1248, // diagonals
+];
+
+const fn lines_through(cell: usize) -> u16 {
+    let mut lines = 0;
+    let mut line = 0;
+    while line < LINES.len() {
+        if LINES[line] & (1 << cell) != 0 {
+            lines |= 1 << line;
+        }
+        line += 1;
+    }
+    lines
+}
+
+// For every cell, the indices in `LINES` of the lines going through it, as a bit set
+const CELL_LINES: [u16; BOARD_SIZE * BOARD_SIZE] = {
+    let mut cell_lines = [0; BOARD_SIZE * BOARD_SIZE];
+    let mut cell = 0;
+    while cell < cell_lines.len() {
+        cell_lines[cell] = lines_through(cell);
+        cell += 1;
+    }
+    cell_lines
+};
+
+// The indices of the bits set in `mask`, lowest first
+fn bits(mask: u16) -> impl Iterator<Item = usize> {
+    (0..16).filter(move |bit| mask & (1 << bit) != 0)
+}
+
+fn cell_coordinate(cell: usize) -> Coordinate {
+    Coordinate::new(cell / BOARD_SIZE, cell % BOARD_SIZE)
+}
+
+fn cell_index(position: Coordinate) -> Result<usize, QuartoError> {
+    if position.row >= BOARD_SIZE || position.column >= BOARD_SIZE {
+        return Err(QuartoError::OutOfBounds(position));
+    }
+    Ok(position.row * BOARD_SIZE + position.column)
+}
+
+/// Same game as [`Game`], stored in a few integers so it's cheap to copy and check for wins.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BitGame {
+    occupied: u16,
+    // Bit `cell` of `attributes[attribute]` is set when the piece at `cell` has that attribute
+    attributes: [u16; N_PROPERTIES],
+    // Bit `piece` is set when the piece is neither on the board nor waiting to be placed
+    pieces_left: u16,
+    in_hand: Option<u8>,
+    player_turn: Player,
+    result: GameResult,
+    // Zobrist hash of the position, kept up to date as actions are played like `Game::key`
+    key: u64,
+}
+
+// Equal games have equal keys, so hashing the key is enough
+impl Hash for BitGame {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.key);
+    }
+}
+
+impl Default for BitGame {
+    fn default() -> Self {
+        BitGame::new()
+    }
+}
+
+impl BitGame {
+    /// The empty board, with Player1 choosing the first piece.
+    pub fn new() -> BitGame {
+        BitGame {
+            occupied: 0,
+            attributes: [0; N_PROPERTIES],
+            pieces_left: ALL_PIECES,
+            in_hand: None,
+            player_turn: Player::Player1,
+            result: GameResult::InProgress,
+            key: zobrist::player_key(Player::Player1),
+        }
+    }
+
+    /// The player that has to make the next move.
+    pub fn player_turn(&self) -> Player {
+        self.player_turn
+    }
+
+    /// Whether a piece has to be chosen or placed, and which one.
+    pub fn stage(&self) -> Stage {
+        match self.in_hand {
+            None => Stage::ChoosingPieceForOponent,
+            Some(piece) => Stage::PlacingPieceGivenOponentChoice(piece_from_index(piece)),
+        }
+    }
+
+    /// Who won, if the game is over.
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    /// The piece at `position`, if any.
+    pub fn get(&self, position: Coordinate) -> Result<Option<Piece>, QuartoError> {
+        let cell = cell_index(position)?;
+        if self.occupied & (1 << cell) == 0 {
+            return Ok(None);
+        }
+
+        let mut piece = [false; N_PROPERTIES];
+        for (attribute, mask) in piece.iter_mut().zip(self.attributes) {
+            *attribute = mask & (1 << cell) != 0;
+        }
+        Ok(Some(piece))
+    }
+
+    /// Pieces that are neither on the board nor waiting to be placed, in index order.
+    pub fn get_pieces_left(&self) -> Vec<Piece> {
+        bits(self.pieces_left)
+            .map(|piece| piece_from_index(piece as u8))
+            .collect()
+    }
+
+    /// Cells without a piece, row by row.
+    pub fn get_empty_places(&self) -> Vec<Coordinate> {
+        bits(!self.occupied).map(cell_coordinate).collect()
+    }
+
+    /// Every action the player to move can play, none once the game is over. They come in the
+    /// same order as in [`Game::legal_actions`].
+    pub fn legal_actions(&self) -> Vec<QuartoAction> {
+        if self.result != GameResult::InProgress {
+            return vec![];
+        }
+        match self.in_hand {
+            None => bits(self.pieces_left)
+                .map(|piece| QuartoAction::Choose(piece_from_index(piece as u8)))
+                .collect(),
+            Some(_) => bits(!self.occupied)
+                .map(|cell| QuartoAction::Put(cell_coordinate(cell)))
+                .collect(),
+        }
+    }
+
+    /// Number of pieces that are neither on the board nor waiting to be placed.
+    pub fn pieces_left_count(&self) -> usize {
+        self.pieces_left.count_ones() as usize
+    }
+
+    /// Same hash as [`Game::key`] for the same position.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    // The key computed from scratch
+    fn compute_key(&self) -> u64 {
+        zobrist::hash_cells(
+            (0..BOARD_SIZE * BOARD_SIZE).filter_map(|cell| Some((cell, self.piece_index(cell)?))),
+            self.in_hand,
+            self.player_turn,
+        )
+    }
+
+    /// The representative of every position equivalent to this one, see [`canonical`].
+    ///
+    /// [`canonical`]: crate::canonical
+    pub fn canonical(&self) -> BitGame {
+        let mut cells = [EMPTY; BOARD_SIZE * BOARD_SIZE];
+        for (cell, piece) in cells.iter_mut().enumerate() {
+            if let Some(index) = self.piece_index(cell) {
+                *piece = index;
+            }
+        }
+        let (cells, in_hand) = symmetry::canonical_cells(&cells, self.in_hand);
+        BitGame::from_cells(&cells, in_hand, self.player_turn, self.result)
+    }
+
+    // A position with the piece of every cell, `EMPTY` for none
+    fn from_cells(
+        cells: &[u8; BOARD_SIZE * BOARD_SIZE],
+        in_hand: Option<u8>,
+        player_turn: Player,
+        result: GameResult,
+    ) -> BitGame {
+        let mut game = BitGame {
+            in_hand,
+            player_turn,
+            result,
+            ..BitGame::new()
+        };
+        for (cell, piece) in cells.iter().enumerate() {
+            if *piece != EMPTY {
+                game.place(cell, *piece);
+            }
+        }
+        if let Some(piece) = in_hand {
+            game.pieces_left &= !(1 << piece);
+        }
+        game.key = game.compute_key();
+        game
+    }
+
+    // The piece of every cell, 4 bits each with the first cell lowest, and the cells that have one,
+    // as in the entries of compact databases
+    pub(crate) fn packed_cells(&self) -> (u64, u16) {
+        let cells = bits(self.occupied).fold(0, |cells, cell| {
+            let piece = self.piece_index(cell).expect("the cell is occupied");
+            cells | u64::from(piece) << (4 * cell)
+        });
+        (cells, self.occupied)
+    }
+
+    // The index of the piece waiting to be placed
+    pub(crate) fn in_hand(&self) -> Option<u8> {
+        self.in_hand
+    }
+
+    fn piece_index(&self, cell: usize) -> Option<u8> {
+        if self.occupied & (1 << cell) == 0 {
+            return None;
+        }
+        Some(
+            self.attributes
+                .iter()
+                .fold(0, |piece, mask| piece << 1 | ((mask >> cell) & 1) as u8),
+        )
+    }
+
+    // Puts `piece` at `cell`, taking it from the pieces left if it's still there
+    fn place(&mut self, cell: usize, piece: u8) {
+        self.occupied |= 1 << cell;
+        self.pieces_left &= !(1 << piece);
+        for (attribute, mask) in self.attributes.iter_mut().enumerate() {
+            if piece & (1 << (N_PROPERTIES - attribute - 1)) != 0 {
+                *mask |= 1 << cell;
+            }
+        }
+    }
+
+    /// Whether any full line going through `position` has pieces sharing an attribute.
+    pub fn check_if_won(&self, position: Coordinate) -> bool {
+        match cell_index(position) {
+            Ok(cell) => self.check_cell(cell),
+            Err(_) => false,
+        }
+    }
+
+    fn check_cell(&self, cell: usize) -> bool {
+        let mut lines = CELL_LINES[cell];
+        while lines != 0 {
+            let line = LINES[lines.trailing_zeros() as usize];
+            lines &= lines - 1;
+
+            if self.occupied & line != line {
+                continue;
+            }
+            // The pieces match if they all have the attribute, or none of them has it
+            if self.attributes.iter().any(|mask| {
+                let with_attribute = mask & line;
+                with_attribute == line || with_attribute == 0
+            }) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Plays `action`, like [`Game::play`].
+    pub fn play(&mut self, action: QuartoAction) -> Result<(), QuartoError> {
+        match action {
+            QuartoAction::Choose(piece) => self.choose(piece),
+            QuartoAction::Put(position) => self.put(position),
+        }
+    }
+
+    /// Gives `piece` to the opponent, like [`Game::choose`].
+    pub fn choose(&mut self, piece: Piece) -> Result<(), QuartoError> {
+        if self.result != GameResult::InProgress {
+            return Err(QuartoError::GameOver(self.result));
+        }
+        let index = piece_to_index(piece);
+        if self.pieces_left & (1 << index) == 0 {
+            return Err(QuartoError::PieceUnavailable(piece));
+        }
+        if self.in_hand.is_some() {
+            return Err(QuartoError::WrongStage);
+        }
+
+        self.pieces_left &= !(1 << index);
+        self.in_hand = Some(index);
+        self.key ^= zobrist::in_hand_key(Some(index))
+            ^ zobrist::player_key(self.player_turn)
+            ^ zobrist::player_key(self.player_turn.opponent());
+        self.player_turn = self.player_turn.opponent();
+        Ok(())
+    }
+
+    /// Places the piece in hand at `position`, like [`Game::put`].
+    pub fn put(&mut self, position: Coordinate) -> Result<(), QuartoError> {
+        if self.result != GameResult::InProgress {
+            return Err(QuartoError::GameOver(self.result));
+        }
+        let Some(piece) = self.in_hand else {
+            return Err(QuartoError::WrongStage);
+        };
+        let cell = cell_index(position)?;
+        if self.occupied & (1 << cell) != 0 {
+            return Err(QuartoError::CellOccupied(position));
+        }
+
+        self.place(cell, piece);
+        self.key ^= zobrist::cell_index_key(cell, piece);
+
+        // A win takes precedence over a draw, as in `Game::put`
+        if self.check_cell(cell) {
+            self.result = GameResult::PlayerWon(self.player_turn);
+        } else if self.pieces_left == 0 {
+            self.result = GameResult::Draw;
+        } else {
+            self.in_hand = None;
+            self.key ^= zobrist::in_hand_key(Some(piece));
+        }
+        Ok(())
+    }
+
+    /// The same position as a [`Game`], with no history.
+    pub fn to_game(&self) -> Game {
+        let mut board = Board::new();
+        for row in 0..BOARD_SIZE {
+            for column in 0..BOARD_SIZE {
+                board.grid[row][column] = self
+                    .get(Coordinate { row, column })
+                    .expect("every cell is inside the board");
+            }
+        }
+
+        Game::from_parts(
+            board,
+            GameState {
+                player_turn: self.player_turn,
+                stage: self.stage(),
+                result: self.result,
+            },
+            self.get_pieces_left().into_iter().collect(),
+        )
+    }
+}
+
+impl From<&Game> for BitGame {
+    fn from(game: &Game) -> Self {
+        let mut bit_game = BitGame::new();
+        for (row_index, row) in game.board.grid.iter().enumerate() {
+            for (column_index, cell) in row.iter().enumerate() {
+                if let Some(piece) = cell {
+                    let cell = row_index * BOARD_SIZE + column_index;
+                    bit_game.occupied |= 1 << cell;
+                    for (attribute, mask) in bit_game.attributes.iter_mut().enumerate() {
+                        if piece[attribute] {
+                            *mask |= 1 << cell;
+                        }
+                    }
+                }
+            }
+        }
+
+        bit_game.pieces_left = game
+            .pieces_left
+            .iter()
+            .fold(0, |mask, piece| mask | 1 << piece_to_index(*piece));
+        bit_game.in_hand = match game.game_state.stage {
+            Stage::ChoosingPieceForOponent => None,
+            Stage::PlacingPieceGivenOponentChoice(piece) => Some(piece_to_index(piece)),
+        };
+        bit_game.player_turn = game.game_state.player_turn;
+        bit_game.result = game.game_state.result;
+        bit_game.key = game.key;
+        bit_game
+    }
+}
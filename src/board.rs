@@ -1,4 +1,5 @@
 use super::coordinate::Coordinate;
+use super::error::QuartoError;
 
 use std::fmt::Debug;
 
@@ -17,8 +18,9 @@ pub(crate) fn empty_grid<T>() -> Grid<T> {
     [empty_row(), empty_row(), empty_row(), empty_row()]
 }
 
+/// A square grid where every cell is either empty or holds a `T`.
 #[derive(Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct Board<T> {
+pub struct Board<T> {
     pub(crate) grid: Grid<T>,
 }
 
@@ -27,18 +29,17 @@ impl<T: Copy + Debug> Board<T> {
         Board { grid: empty_grid() }
     }
 
-    pub(crate) fn get(&self, position: Coordinate) -> Result<Option<T>, String> {
-        if position.row > QUATRO || position.column > QUATRO {
-            return Err(format!(
-                "Position out of bounds: you requested {position:#?} but board size is {QUATRO}"
-            ));
+    /// Returns the content of the cell at `position`, or an error if it's outside the board.
+    pub fn get(&self, position: Coordinate) -> Result<Option<T>, QuartoError> {
+        if position.row >= BOARD_SIZE || position.column >= BOARD_SIZE {
+            return Err(QuartoError::OutOfBounds(position));
         }
         Ok(self.grid[position.row][position.column])
     }
 
-    pub(crate) fn put(&mut self, piece: T, position: Coordinate) -> Result<(), String> {
+    pub(crate) fn put(&mut self, piece: T, position: Coordinate) -> Result<(), QuartoError> {
         match self.get(position)? {
-            Some(piece) => Err(format!("Place occupied by {piece:#?}")),
+            Some(_) => Err(QuartoError::CellOccupied(position)),
             None => {
                 self.grid[position.row][position.column] = Some(piece);
                 Ok(())
@@ -46,28 +47,27 @@ impl<T: Copy + Debug> Board<T> {
         }
     }
 
-    #[allow(dead_code)]
-    pub(crate) fn remove(&mut self, position: Coordinate) -> Result<T, String> {
+    pub(crate) fn remove(&mut self, position: Coordinate) -> Result<T, QuartoError> {
         match self.get(position)? {
             Some(piece) => {
                 self.grid[position.row][position.column] = None;
                 Ok(piece)
             }
-            None => Err("Place is empty".to_string()),
+            None => Err(QuartoError::CellEmpty(position)),
         }
     }
 
-    pub(crate) fn empty_spaces(&self) -> Vec<Coordinate> {
+    /// Returns every empty cell, row by row.
+    pub fn empty_spaces(&self) -> Vec<Coordinate> {
         let mut result = Vec::new();
 
         for (row_index, row) in self.grid.iter().enumerate() {
             for (column_index, value) in row.iter().enumerate() {
-                match value {
-                    None => result.push(Coordinate {
+                if value.is_none() {
+                    result.push(Coordinate {
                         column: column_index,
                         row: row_index,
-                    }),
-                    Some(_) => (),
+                    });
                 }
             }
         }
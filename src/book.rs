@@ -0,0 +1,165 @@
+//! Opening books: solved values in a file that is mapped into memory and searched in place, so
+//! even a book with millions of positions is ready to use as soon as it's opened.
+//!
+//! A book is a header followed by the entries, in little endian:
+//!
+//! | Bytes | Field                                                          |
+//! |-------|----------------------------------------------------------------|
+//! | 8     | Magic, `QUARTOBK`                                              |
+//! | 2     | Format version                                                 |
+//! | 1     | Board size                                                     |
+//! | 1     | Number of attributes of the pieces                             |
+//! | 1     | Rule variant, 0 for the standard rules                         |
+//! | 3     | Reserved, 0                                                    |
+//! | 8     | Number of entries                                              |
+//!
+//! Every entry is the [`Game::key`] of a canonical position, see [`canonical`], as a `u64`
+//! followed by its value as an `i16`. Entries are sorted by key. Only the keys of the positions
+//! are stored, so two positions with the same key would share a value, which is unlikely enough
+//! with 64 bits to be ignored.
+
+use crate::database::write_atomically;
+use crate::game::Game;
+use crate::symmetry::canonical;
+use crate::{BOARD_SIZE, N_PROPERTIES};
+
+use memmap2::Mmap;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+const MAGIC: [u8; 8] = *b"QUARTOBK";
+// Follows the version of the values, see `database::VALUES_VERSION`
+const VERSION: u16 = 2;
+
+const HEADER_SIZE: usize = 24;
+const ENTRY_SIZE: usize = 10;
+
+/// A read-only book of solved positions.
+pub struct Book {
+    map: Mmap,
+    len: usize,
+}
+
+impl Book {
+    /// Opens the book at `path`. Only the header is read, entries are read from the file as
+    /// they are looked up.
+    pub fn open(path: &Path) -> io::Result<Book> {
+        let file = File::open(path)?;
+        // SAFETY: the map is only read, and books are only written by replacing the whole file
+        // (see `Book::write`), which leaves the mapped one untouched
+        let map = unsafe { Mmap::map(&file)? };
+
+        if map.len() < HEADER_SIZE || map[..8] != MAGIC {
+            return Err(invalid_data("not a Quarto book"));
+        }
+        let version = u16::from_le_bytes([map[8], map[9]]);
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("book version {version} is not the supported {VERSION}"),
+            ));
+        }
+        if usize::from(map[10]) != BOARD_SIZE || usize::from(map[11]) != N_PROPERTIES {
+            return Err(invalid_data(format!(
+                "book for a {0}x{0} board with {1} attributes per piece",
+                map[10], map[11]
+            )));
+        }
+        if map[12] != 0 {
+            return Err(invalid_data(format!("unknown rule variant {}", map[12])));
+        }
+
+        let len = u64::from_le_bytes(map[16..24].try_into().expect("8 bytes"));
+        if len.checked_mul(ENTRY_SIZE as u64) != Some((map.len() - HEADER_SIZE) as u64) {
+            return Err(invalid_data(format!(
+                "expected {len} entries, found {} bytes of them",
+                map.len() - HEADER_SIZE
+            )));
+        }
+        Ok(Book {
+            map,
+            len: len as usize,
+        })
+    }
+
+    /// Writes the values of `memory` as a book at `path`, returning the number of entries.
+    /// Equivalent positions are stored once.
+    pub fn write(memory: &HashMap<Game, i32>, path: &Path) -> io::Result<usize> {
+        let mut entries = memory
+            .iter()
+            .map(|(game, value)| {
+                let value = i16::try_from(*value).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("value {value} doesn't fit in a book entry"),
+                    )
+                })?;
+                Ok((canonical(game).key(), value))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort_unstable();
+        entries.dedup_by_key(|(key, _)| *key);
+
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + entries.len() * ENTRY_SIZE);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.push(BOARD_SIZE as u8);
+        bytes.push(N_PROPERTIES as u8);
+        // Standard rules
+        bytes.push(0);
+        bytes.extend_from_slice(&[0; 3]);
+        bytes.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+        for (key, value) in &entries {
+            bytes.extend_from_slice(&key.to_le_bytes());
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        write_atomically(path, &bytes)?;
+        Ok(entries.len())
+    }
+
+    /// Number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the book has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The value of `game`, or of any position equivalent to it, if it's in the book.
+    pub fn get(&self, game: &Game) -> Option<i32> {
+        self.get_canonical(canonical(game).key())
+    }
+
+    /// Like [`Book::get`], with the key of a position already in its canonical form.
+    pub(crate) fn get_canonical(&self, key: u64) -> Option<i32> {
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let (middle_key, value) = self.entry(middle);
+            match middle_key.cmp(&key) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(i32::from(value)),
+            }
+        }
+        None
+    }
+
+    fn entry(&self, index: usize) -> (u64, i16) {
+        let start = HEADER_SIZE + index * ENTRY_SIZE;
+        let bytes = &self.map[start..start + ENTRY_SIZE];
+        (
+            u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes")),
+            i16::from_le_bytes([bytes[8], bytes[9]]),
+        )
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
@@ -0,0 +1,209 @@
+//! Searches that take hours or days, like solving the game from the empty board. The values found
+//! are handed over periodically to be saved, so an interrupted search can be resumed from them.
+
+use crate::alpha_beta::{alpha_beta, Window};
+use crate::bitboard::BitGame;
+use crate::game::{Game, QuartoAction};
+use crate::minimax::{Minimax, Side};
+use crate::quarto_minimax::{is_exact, memory_key, QuartoMinimax};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Statistics about a search so far.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// States whose value wasn't known and had to be searched.
+    pub searched: u64,
+    /// Values known, including the ones known before the search started.
+    pub stored: usize,
+    /// Time since the search started.
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seconds = self.elapsed.as_secs_f64();
+        write!(
+            f,
+            "{seconds:.0}s: {} states searched ({:.0} per second), {} values stored",
+            self.searched,
+            self.searched as f64 / seconds.max(f64::EPSILON),
+            self.stored,
+        )
+    }
+}
+
+/// What is known about the value of a state that was only searched within a window: it's between
+/// `lower` and `upper`, both included.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bounds {
+    /// The value is at least this.
+    pub lower: i32,
+    /// The value is at most this.
+    pub upper: i32,
+}
+
+impl Bounds {
+    const UNKNOWN: Bounds = Bounds {
+        lower: i32::MIN,
+        upper: i32::MAX,
+    };
+}
+
+/// The solver, counting what it does and calling the checkpoint every now and then.
+struct Checkpointed<'a, F> {
+    solver: &'a mut QuartoMinimax,
+    // Most states are searched within a window that only gives a bound of their value. Those
+    // are kept too, otherwise resuming would search them all again
+    bounds: HashMap<BitGame, Bounds>,
+    checkpoint: F,
+    interval: Duration,
+    start: Instant,
+    last_checkpoint: Instant,
+    searched: u64,
+    // The first checkpoint that failed. The search is stopped as fast as possible after it, and
+    // nothing found from then on is remembered
+    error: Option<io::Error>,
+}
+
+impl<F> Checkpointed<'_, F> {
+    fn progress(&self) -> Progress {
+        Progress {
+            searched: self.searched,
+            stored: self
+                .solver
+                .table()
+                .map_or(self.solver.memory_len(), |table| table.len()),
+            elapsed: self.start.elapsed(),
+        }
+    }
+
+    // The bounds keyed by the games they are saved as
+    fn bounds(&self) -> HashMap<Game, Bounds> {
+        self.bounds
+            .iter()
+            .map(|(state, bounds)| (state.to_game(), *bounds))
+            .collect()
+    }
+}
+
+impl<F> Minimax<BitGame, QuartoAction> for Checkpointed<'_, F>
+where
+    F: FnMut(&mut QuartoMinimax, &HashMap<Game, Bounds>, &Progress) -> io::Result<()>,
+{
+    // After a failed checkpoint every state is terminal, with a meaningless value
+    fn utility(&self, state: &BitGame) -> i32 {
+        match self.error {
+            Some(_) => 0,
+            None => self.solver.utility(state),
+        }
+    }
+
+    fn terminal(&self, state: &BitGame) -> bool {
+        self.error.is_some() || self.solver.terminal(state)
+    }
+
+    fn actions(&self, state: &BitGame) -> Vec<QuartoAction> {
+        self.solver.actions(state)
+    }
+
+    fn result(&self, state: &BitGame, action: QuartoAction) -> BitGame {
+        Minimax::result(&*self.solver, state, action)
+    }
+
+    fn to_move(&self, state: &BitGame) -> Side {
+        self.solver.to_move(state)
+    }
+
+    fn best_possible(&self, state: &BitGame) -> Option<i32> {
+        self.solver.best_possible(state)
+    }
+
+    fn memoized_within<G>(&mut self, state: &BitGame, window: Window, search: G) -> i32
+    where
+        G: FnOnce(&mut Self) -> i32,
+    {
+        let key = memory_key(state, self.solver.use_symmetries);
+        if let Some(value) = self.solver.recall(&key) {
+            return value;
+        }
+        let bounds = self.bounds.get(&key).copied().unwrap_or(Bounds::UNKNOWN);
+        if bounds.lower >= window.beta {
+            return bounds.lower;
+        }
+        if bounds.upper <= window.alpha {
+            return bounds.upper;
+        }
+
+        self.searched += 1;
+        let value = search(self);
+        if self.error.is_some() {
+            return value;
+        }
+        if is_exact(state, window, value) {
+            self.bounds.remove(&key);
+            self.solver.remember(key, value);
+        } else if value <= window.alpha {
+            let upper = bounds.upper.min(value);
+            self.bounds.insert(key, Bounds { upper, ..bounds });
+        } else {
+            let lower = bounds.lower.max(value);
+            self.bounds.insert(key, Bounds { lower, ..bounds });
+        }
+
+        if self.last_checkpoint.elapsed() >= self.interval {
+            let progress = self.progress();
+            let bounds = self.bounds();
+            if let Err(error) = (self.checkpoint)(self.solver, &bounds, &progress) {
+                self.error = Some(error);
+            }
+            self.last_checkpoint = Instant::now();
+        }
+        value
+    }
+}
+
+/// Solves `state` with alpha-beta, calling `checkpoint` at least `interval` apart with the solver,
+/// the bounds found and the progress so far, and once more at the end. The solver's memory only
+/// has exact values at any point, so saving it and the bounds in a checkpoint and solving again
+/// with them later resumes the search. If a checkpoint fails the search stops, returning that
+/// error.
+pub fn solve_with_checkpoints<F>(
+    solver: &mut QuartoMinimax,
+    bounds: &mut HashMap<Game, Bounds>,
+    state: &Game,
+    interval: Duration,
+    checkpoint: F,
+) -> io::Result<i32>
+where
+    F: FnMut(&mut QuartoMinimax, &HashMap<Game, Bounds>, &Progress) -> io::Result<()>,
+{
+    let start = Instant::now();
+    let mut checkpointed = Checkpointed {
+        solver,
+        bounds: bounds
+            .iter()
+            .map(|(game, bounds)| (BitGame::from(game), *bounds))
+            .collect(),
+        checkpoint,
+        interval,
+        start,
+        last_checkpoint: start,
+        searched: 0,
+        error: None,
+    };
+
+    let value = alpha_beta(&mut checkpointed, &BitGame::from(state), Window::FULL);
+    *bounds = checkpointed.bounds();
+    if let Some(error) = checkpointed.error {
+        return Err(error);
+    }
+
+    let progress = checkpointed.progress();
+    (checkpointed.checkpoint)(checkpointed.solver, bounds, &progress)?;
+    Ok(value)
+}
@@ -0,0 +1,536 @@
+//! The command line interface of the `quatro-in-rust` binary, see [`USAGE`].
+
+use crate::alpha_beta::Window;
+use crate::book::Book;
+use crate::checkpoint::solve_with_checkpoints;
+use crate::compact::{read_header, SymmetryMode};
+use crate::database::{
+    is_legacy_database, migrate_legacy_values, read_bincode, read_database, read_legacy_database,
+    write_bincode, write_database, DatabaseFormat,
+};
+use crate::depth_limited::{depth_limited, QuartoEvaluator, WIN_SCORE};
+use crate::game::{Game, Player};
+use crate::interactive::{self, Controller, Search};
+use crate::iterative_deepening::iterative_deepening;
+use crate::journal::Journal;
+use crate::mcts::Limits;
+use crate::minimax::Minimax;
+use crate::quarto_minimax::QuartoMinimax;
+use crate::record::GameRecord;
+use crate::symmetry::canonical;
+use crate::transposition::TranspositionTable;
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Help printed by `help`, `--help` or `-h`, and on invalid arguments.
+pub const USAGE: &str = "\
+Usage: quatro-in-rust [OPTIONS] <COMMAND>
+
+Commands:
+  play                     Play a game on the terminal
+  solve <position>         Solve a position given in notation, e.g. \"0.../..../..../.... - 2 *\"
+  solve-opening            Solve the game from the empty board, saving the memo periodically.
+                           Running it again resumes from the saved memo
+  analyze <game-record>    Print the value after every action of a game record file. Values not
+                           in the memo or the book are estimated with --depth or --time, or
+                           solved with --exhaustive
+  db export <file>         Write the memo to <file>, in the format given by its extension. A memo
+                           of an older version is migrated with
+                           --memo state_to_value.bin db export state_to_value.qdb
+  db import <file>         Merge the entries of <file> into the memo, in any format. Files of an
+                           older version only tell who wins: their wins are solved again
+  db stats                 Print statistics about the memo
+  db book <file>           Write the memo as an opening book to <file>, see --book
+  help                     Print this help, also printed by --help or -h
+
+Options:
+  --memo <path>            Memo file path [default: state_to_value.qdb]. Values found since it
+                           was last saved are logged to <path>.log
+  --format <compact|json|bincode>
+                           Memo file format [default: the one of the memo file if it exists,
+                           otherwise from its extension]
+  --player1 <human|computer>
+  --player2 <human|computer>
+                           Who controls each player when playing [default: human]
+  --depth <actions>        Make the computer look only this many actions ahead
+  --time <seconds>         Make the computer look as many actions ahead as it can in this time
+                           [default: 5 unless --depth or --exhaustive is given]
+  --exhaustive             Make the computer search until the end of the game, which is only
+                           feasible towards the end
+  --mcts                   Make the computer play with Monte Carlo tree search instead, within
+                           --time and --iterations [default: 10000 iterations]
+  --iterations <count>     Number of Monte Carlo tree search iterations per move
+  --seed <number>          Seed for Monte Carlo tree search [default: from the clock]
+  --threads <count>        Threads used to solve [default: the number of CPUs]
+  --all-actions            Make solve also print the value of every action, solving them one
+                           after another
+  --checkpoint-interval <seconds>
+                           Time between saves of solve-opening [default: 600]
+  --table-memory <MiB>     Solve with a table of this size instead of the memo, which is then
+                           neither read nor written
+  --book <file>            Look up values in this opening book before searching them. With
+                           --table-memory the computer starts playing without loading anything";
+
+struct Options {
+    memo: PathBuf,
+    format: Option<DatabaseFormat>,
+    players: [Controller; 2],
+    depth: Option<u32>,
+    time: Option<Duration>,
+    exhaustive: bool,
+    mcts: bool,
+    all_actions: bool,
+    iterations: Option<u64>,
+    seed: Option<u64>,
+    threads: usize,
+    table_memory: Option<usize>,
+    book: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    help: bool,
+    arguments: Vec<String>,
+}
+
+const DEFAULT_MCTS_ITERATIONS: u64 = 10_000;
+// Searching until the end of the game from the start never finishes, so the computer has a budget
+const DEFAULT_TIME: Duration = Duration::from_secs(5);
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options {
+            memo: PathBuf::from("state_to_value.qdb"),
+            format: None,
+            players: [Controller::Human, Controller::Human],
+            depth: None,
+            time: None,
+            exhaustive: false,
+            mcts: false,
+            all_actions: false,
+            iterations: None,
+            seed: None,
+            threads: thread::available_parallelism().map_or(1, usize::from),
+            table_memory: None,
+            book: None,
+            checkpoint_interval: Duration::from_secs(600),
+            help: false,
+            arguments: vec![],
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {arg}"))
+            };
+            match arg.as_str() {
+                "--memo" => options.memo = PathBuf::from(value()?),
+                "--format" => options.format = Some(value()?.parse()?),
+                "--player1" => options.players[0] = parse_controller(value()?)?,
+                "--player2" => options.players[1] = parse_controller(value()?)?,
+                "--depth" => options.depth = Some(parse_number(arg, value()?)?),
+                "--time" => options.time = Some(parse_seconds(arg, value()?)?),
+                "--exhaustive" => options.exhaustive = true,
+                "--mcts" => options.mcts = true,
+                "--all-actions" => options.all_actions = true,
+                "--iterations" => options.iterations = Some(parse_number(arg, value()?)?),
+                "--seed" => options.seed = Some(parse_number(arg, value()?)?),
+                "--threads" => options.threads = parse_number(arg, value()?)?,
+                "--table-memory" => options.table_memory = Some(parse_number(arg, value()?)?),
+                "--book" => options.book = Some(PathBuf::from(value()?)),
+                "--checkpoint-interval" => {
+                    options.checkpoint_interval = parse_seconds(arg, value()?)?;
+                }
+                "--help" | "-h" => options.help = true,
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {arg}")),
+                _ => options.arguments.push(arg.clone()),
+            }
+        }
+
+        Ok(options)
+    }
+
+    fn search(&self) -> Search {
+        if self.mcts {
+            let iterations = match (self.iterations, self.time) {
+                (None, None) => Some(DEFAULT_MCTS_ITERATIONS),
+                (iterations, _) => iterations,
+            };
+            let seed = self.seed.unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |time| time.as_nanos() as u64)
+            });
+            return Search::Mcts {
+                limits: Limits {
+                    iterations,
+                    time: self.time,
+                },
+                seed,
+            };
+        }
+
+        match (self.exhaustive, self.time, self.depth) {
+            (true, _, _) => Search::Exhaustive,
+            (false, Some(budget), _) => Search::TimeLimited(budget),
+            (false, None, Some(depth)) => Search::DepthLimited(depth),
+            (false, None, None) => Search::TimeLimited(DEFAULT_TIME),
+        }
+    }
+
+    fn memo_format(&self) -> DatabaseFormat {
+        self.format.unwrap_or_else(|| {
+            DatabaseFormat::detect(&self.memo)
+                .unwrap_or_else(|_| DatabaseFormat::from_path(&self.memo))
+        })
+    }
+
+    // A file next to the memo, named after it
+    fn memo_sibling(&self, extension: &str) -> PathBuf {
+        let mut path = self.memo.as_os_str().to_owned();
+        path.push(extension);
+        PathBuf::from(path)
+    }
+
+    // Values found since the memo was last saved are in the journal
+    fn journal_path(&self) -> PathBuf {
+        self.memo_sibling(".log")
+    }
+
+    // A missing memo file is not an error: there's just nothing solved yet
+    fn load_saved_memo(&self) -> io::Result<HashMap<Game, i32>> {
+        if !self.memo.exists() {
+            return Ok(HashMap::new());
+        }
+        read_memo_file(&self.memo, self.memo_format())
+    }
+
+    fn load_memo(&self) -> io::Result<HashMap<Game, i32>> {
+        let mut memory = self.load_saved_memo()?;
+        memory.extend(Journal::read(&self.journal_path())?);
+        Ok(memory)
+    }
+
+    fn save_memo(&self, memory: &HashMap<Game, i32>, symmetries: SymmetryMode) -> io::Result<()> {
+        write_database(memory, &self.memo, self.memo_format(), symmetries)
+    }
+
+    fn solver(&self) -> io::Result<QuartoMinimax> {
+        let mut qmm = match self.table_memory {
+            Some(megabytes) => {
+                let table = TranspositionTable::with_memory(megabytes << 20);
+                QuartoMinimax::with_table(table)
+            }
+            None => {
+                let (journal, logged) = Journal::open(&self.journal_path())?;
+                let mut memory = self.load_saved_memo()?;
+                memory.extend(logged);
+                let mut qmm = QuartoMinimax::new(memory);
+                qmm.set_journal(journal);
+                qmm
+            }
+        };
+        if let Some(book) = &self.book {
+            qmm.set_book(Book::open(book)?);
+        }
+        Ok(qmm)
+    }
+
+    // Once the memo is saved the journal can start over. Values stored in a table can't be saved
+    fn save_solver(&self, qmm: &mut QuartoMinimax) -> io::Result<()> {
+        if qmm.table().is_some() {
+            return Ok(());
+        }
+        self.save_memo(&qmm.memory(), qmm.symmetry_mode())?;
+        match qmm.journal_mut() {
+            Some(journal) => journal.clear(),
+            None => Ok(()),
+        }
+    }
+}
+
+// The solvers made here use symmetries, so memos only keep canonical positions
+const SYMMETRIES: SymmetryMode = SymmetryMode::Canonical;
+
+// Databases of positions as played are made canonical, otherwise the solver would never find them.
+// Databases of version 1 are migrated
+fn read_memo_file(path: &Path, format: DatabaseFormat) -> io::Result<HashMap<Game, i32>> {
+    if is_legacy_database(path, format)? {
+        let legacy = read_legacy_database(path, format)?;
+        println!(
+            "Migrating the {} values of {}, solving their wins again",
+            legacy.len(),
+            path.display()
+        );
+        let mut qmm = QuartoMinimax::new(HashMap::new());
+        migrate_legacy_values(&mut qmm, &legacy)?;
+        return Ok(qmm.memory());
+    }
+    let memory = read_database(path, format)?;
+    if format == DatabaseFormat::Compact && read_header(path)?.symmetries != SYMMETRIES {
+        return Ok(memory
+            .into_iter()
+            .map(|(game, value)| (canonical(&game), value))
+            .collect());
+    }
+    Ok(memory)
+}
+
+fn parse_number<T: FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value {value:?} for {option}"))
+}
+
+// Negative, infinite and NaN durations are rejected like any other invalid value
+fn parse_seconds(option: &str, value: &str) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(parse_number(option, value)?)
+        .map_err(|_| format!("Invalid value {value:?} for {option}"))
+}
+
+// The value of `state` followed by what it means, e.g. "30, Player1 wins in 3 actions"
+fn describe(state: &Game, value: i32) -> String {
+    let Some(plies) = QuartoMinimax::plies_to_win(state, value) else {
+        return format!("{value}, draw");
+    };
+    let winner = match value > 0 {
+        true => Player::Player1,
+        false => Player::Player2,
+    };
+    // Counted in actions, not in the moves of a game record, which choose and put a piece
+    match plies {
+        0 => format!("{value}, {winner:?} won"),
+        1 => format!("{value}, {winner:?} wins in 1 action"),
+        _ => format!("{value}, {winner:?} wins in {plies} actions"),
+    }
+}
+
+fn parse_controller(s: &str) -> Result<Controller, String> {
+    match s {
+        "human" => Ok(Controller::Human),
+        "computer" => Ok(Controller::Computer),
+        _ => Err(format!("Unknown player controller {s:?}")),
+    }
+}
+
+/// Runs the command described by `args`, which shouldn't include the program name.
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = Options::parse(args)?;
+    let arguments = options
+        .arguments
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+
+    if options.help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    match arguments[..] {
+        ["help"] => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        ["play"] => play(&options),
+        ["solve", position] => solve(&options, position),
+        ["solve-opening"] => solve_opening(&options),
+        ["analyze", record] => analyze(&options, Path::new(record)),
+        ["db", "export", file] => export(&options, Path::new(file)),
+        ["db", "import", file] => import(&options, Path::new(file)),
+        ["db", "stats"] => stats(&options),
+        ["db", "book", file] => write_book(&options, Path::new(file)),
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn play(options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut qmm = options.solver()?;
+    let stdin = io::stdin();
+    interactive::play(
+        options.players,
+        options.search(),
+        &mut qmm,
+        stdin.lock(),
+        io::stdout(),
+    )?;
+    Ok(())
+}
+
+fn solve(options: &Options, position: &str) -> Result<(), Box<dyn Error>> {
+    let game: Game = position.parse()?;
+    let mut qmm = options.solver()?;
+
+    let start_time = std::time::Instant::now();
+    println!("{}\n", game.board());
+    println!("Position: {}", game.to_notation());
+    match qmm.best_action_parallel(&game, options.threads) {
+        Ok((best_action, value)) => {
+            println!("Value: {}", describe(&game, value));
+            println!("Best action: {best_action:?}");
+            // The parallel search only bounds the actions that can't beat the best one, so listing
+            // their exact values means solving them one at a time
+            if options.all_actions {
+                for (action, value) in qmm.evaluate_all_actions(&game) {
+                    let next_state = qmm.result(&game, action)?;
+                    println!("  {action:?}: {}", describe(&next_state, value));
+                }
+            }
+        }
+        // The game is over
+        Err(_) => println!("Value: {}", describe(&game, qmm.utility(&game))),
+    }
+    println!("Solved in {} seconds", start_time.elapsed().as_secs_f32());
+
+    options.save_solver(&mut qmm)?;
+    Ok(())
+}
+
+fn solve_opening(options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut qmm = options.solver()?;
+    // The bounds found so far are saved next to the memo
+    let bounds_path = options.memo_sibling(".bounds");
+    let mut bounds = match bounds_path.exists() {
+        true => read_bincode(&bounds_path)?,
+        false => HashMap::new(),
+    };
+    if qmm.memory_len() > 0 || !bounds.is_empty() {
+        println!(
+            "Resuming with {} values and {} bounds",
+            qmm.memory_len(),
+            bounds.len()
+        );
+    }
+
+    let value = solve_with_checkpoints(
+        &mut qmm,
+        &mut bounds,
+        &Game::new(),
+        options.checkpoint_interval,
+        |qmm, bounds, progress| {
+            println!("{progress}, {} bounds", bounds.len());
+            options.save_solver(qmm)?;
+            write_bincode(bounds, &bounds_path)
+        },
+    )?;
+    println!("Value of the empty board: {value}");
+    Ok(())
+}
+
+fn analyze(options: &Options, record: &Path) -> Result<(), Box<dyn Error>> {
+    let record: GameRecord = std::fs::read_to_string(record)?.parse()?;
+    let positions = record.replay()?;
+    let search = options.search();
+    if let Search::Mcts { .. } = search {
+        return Err("analyze searches with --depth, --time or --exhaustive, not --mcts".into());
+    }
+    let mut qmm = options.solver()?;
+
+    for (key, value) in &record.metadata {
+        println!("{key}: {value}");
+    }
+    println!("Start: {}", analysis(&mut qmm, search, &positions[0]));
+    for (index, action) in record.actions.iter().enumerate() {
+        let player = positions[index].player_turn();
+        let game = &positions[index + 1];
+        println!(
+            "{player:?} {action:?}: {} ({})",
+            analysis(&mut qmm, search, game),
+            game.to_notation()
+        );
+    }
+
+    options.save_solver(&mut qmm)?;
+    Ok(())
+}
+
+// Positions are only solved with --exhaustive, the others are estimated with `search` unless
+// their value is already known. Estimates beyond `WIN_SCORE` are wins found by the search
+fn analysis(qmm: &mut QuartoMinimax, search: Search, game: &Game) -> String {
+    if let Some(value) = qmm.known_value(game) {
+        return describe(game, value);
+    }
+    let evaluator = QuartoEvaluator::default();
+    let (value, depth) = match search {
+        Search::Exhaustive => return describe(game, qmm.value(game)),
+        Search::DepthLimited(depth) => {
+            let value = depth_limited(&*qmm, &evaluator, game, depth, Window::FULL);
+            (value, depth)
+        }
+        Search::TimeLimited(budget) => match iterative_deepening(&*qmm, &evaluator, game, budget) {
+            Some((_, value, depth)) => (value, depth),
+            None => return "no actions".to_string(),
+        },
+        Search::Mcts { .. } => unreachable!("analyze rejects --mcts"),
+    };
+    match value.abs() >= WIN_SCORE {
+        true => describe(game, value / WIN_SCORE),
+        false => format!("estimated {value} looking {depth} actions ahead"),
+    }
+}
+
+fn export(options: &Options, file: &Path) -> Result<(), Box<dyn Error>> {
+    let memory = options.load_memo()?;
+    write_database(&memory, file, DatabaseFormat::from_path(file), SYMMETRIES)?;
+    println!("Exported {} entries to {}", memory.len(), file.display());
+    Ok(())
+}
+
+fn import(options: &Options, file: &Path) -> Result<(), Box<dyn Error>> {
+    let mut memory = options.load_memo()?;
+    let imported = read_memo_file(file, DatabaseFormat::detect(file)?)?;
+    let count = imported.len();
+    memory.extend(imported);
+    options.save_memo(&memory, SYMMETRIES)?;
+    println!(
+        "Imported {count} entries, the memo now has {}",
+        memory.len()
+    );
+    Ok(())
+}
+
+fn write_book(options: &Options, file: &Path) -> Result<(), Box<dyn Error>> {
+    let memory = options.load_memo()?;
+    let entries = Book::write(&memory, file)?;
+    println!("Wrote {entries} positions to {}", file.display());
+    Ok(())
+}
+
+fn stats(options: &Options) -> Result<(), Box<dyn Error>> {
+    if options.memo_format() == DatabaseFormat::Compact && options.memo.exists() {
+        let header = read_header(&options.memo)?;
+        println!(
+            "Format: version {}, {}x{} board, {} attributes, {:?} rules, {:?} symmetries",
+            header.version,
+            header.board_size,
+            header.board_size,
+            header.n_properties,
+            header.rules,
+            header.symmetries
+        );
+    }
+
+    let memory = options.load_memo()?;
+    println!("Entries: {}", memory.len());
+
+    let mut by_value = BTreeMap::<i32, usize>::new();
+    let mut by_pieces_placed = BTreeMap::<usize, usize>::new();
+    for (game, value) in &memory {
+        *by_value.entry(*value).or_default() += 1;
+        let pieces_placed = crate::BOARD_SIZE * crate::BOARD_SIZE - game.get_empty_places().len();
+        *by_pieces_placed.entry(pieces_placed).or_default() += 1;
+    }
+
+    println!("By value:");
+    for (value, count) in by_value {
+        println!("  {value:>2}: {count}");
+    }
+    println!("By pieces on the board:");
+    for (pieces, count) in by_pieces_placed {
+        println!("  {pieces:>2}: {count}");
+    }
+    Ok(())
+}
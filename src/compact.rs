@@ -0,0 +1,345 @@
+//! Compact database files that describe what they contain, so a file written by another version
+//! of the program is either read correctly or rejected with an error.
+//!
+//! A file is a header followed by the entries. The header, in little endian:
+//!
+//! | Bytes | Field                                                          |
+//! |-------|----------------------------------------------------------------|
+//! | 8     | Magic, `QUARTODB`                                              |
+//! | 2     | Format version                                                 |
+//! | 1     | Board size                                                     |
+//! | 1     | Number of attributes of the pieces                             |
+//! | 1     | Rule variant, see [`RuleVariant`]                              |
+//! | 1     | Symmetry mode, see [`SymmetryMode`]                            |
+//! | 2     | Reserved, 0                                                    |
+//! | 8     | Number of entries                                              |
+//! | 4     | CRC-32 of the header up to here followed by all the entries    |
+//!
+//! Every entry takes 14 bytes:
+//!
+//! | Bytes | Field                                                          |
+//! |-------|----------------------------------------------------------------|
+//! | 8     | The piece of every cell, 4 bits each, the first cell lowest    |
+//! | 2     | Which cells have a piece, the first cell lowest                |
+//! | 1     | The piece waiting to be placed, or `0xff` if none              |
+//! | 1     | The player to move in bit 0, the result in bits 1 and 2: in    |
+//! |       | progress, Player1 won, Player2 won, draw                       |
+//! | 2     | The value, as a signed number                                  |
+//!
+//! Entries are sorted by their encoding, so the same memory always gives the same file.
+
+use crate::bitboard::BitGame;
+use crate::board::Board;
+use crate::coordinate::Coordinate;
+use crate::game::{all_pieces, Game, GameResult, GameState, Player, Stage};
+use crate::piece::{piece_from_index, piece_to_index};
+use crate::{BOARD_SIZE, N_PROPERTIES};
+
+use std::collections::HashMap;
+use std::io;
+
+pub const MAGIC: [u8; 8] = *b"QUARTODB";
+/// The version written, see [`crate::database::VALUES_VERSION`] for the values of version 1. Those
+/// files can't be read anymore.
+pub const VERSION: u16 = 2;
+
+const HEADER_SIZE: usize = 28;
+// The header before the checksum
+const CHECKED_HEADER_SIZE: usize = HEADER_SIZE - 4;
+pub(crate) const ENTRY_SIZE: usize = 14;
+const NO_PIECE: u8 = 0xff;
+
+/// The rules the values were computed with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RuleVariant {
+    /// The rules of [`Game`].
+    Standard,
+}
+
+/// How the positions of a database relate to the ones in a game.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SymmetryMode {
+    /// Positions are stored as they were played.
+    Positions,
+    /// Only canonical positions are stored, see [`canonical`](crate::canonical).
+    Canonical,
+}
+
+/// What a database file says about itself.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DatabaseHeader {
+    /// Version of the file format.
+    pub version: u16,
+    /// Number of rows and columns of the board.
+    pub board_size: u8,
+    /// Number of attributes of every piece.
+    pub n_properties: u8,
+    /// The rules the values were computed with.
+    pub rules: RuleVariant,
+    /// Whether only canonical positions are stored.
+    pub symmetries: SymmetryMode,
+    /// Number of positions stored.
+    pub entries: u64,
+    /// CRC32 of the header before it and of every entry.
+    pub checksum: u32,
+}
+
+impl DatabaseHeader {
+    fn encode(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0; HEADER_SIZE];
+        bytes[..8].copy_from_slice(&MAGIC);
+        bytes[8..10].copy_from_slice(&self.version.to_le_bytes());
+        bytes[10] = self.board_size;
+        bytes[11] = self.n_properties;
+        bytes[12] = match self.rules {
+            RuleVariant::Standard => 0,
+        };
+        bytes[13] = match self.symmetries {
+            SymmetryMode::Positions => 0,
+            SymmetryMode::Canonical => 1,
+        };
+        bytes[16..24].copy_from_slice(&self.entries.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<DatabaseHeader> {
+        if bytes.len() < HEADER_SIZE || bytes[..8] != MAGIC {
+            return Err(invalid_data("not a Quarto database"));
+        }
+
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("database version {version} is not the supported {VERSION}"),
+            ));
+        }
+
+        Ok(DatabaseHeader {
+            version,
+            board_size: bytes[10],
+            n_properties: bytes[11],
+            rules: match bytes[12] {
+                0 => RuleVariant::Standard,
+                rules => return Err(invalid_data(format!("unknown rule variant {rules}"))),
+            },
+            symmetries: match bytes[13] {
+                0 => SymmetryMode::Positions,
+                1 => SymmetryMode::Canonical,
+                mode => return Err(invalid_data(format!("unknown symmetry mode {mode}"))),
+            },
+            entries: u64::from_le_bytes(bytes[16..24].try_into().expect("8 bytes")),
+            checksum: u32::from_le_bytes(bytes[24..28].try_into().expect("4 bytes")),
+        })
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn cell_coordinate(cell: usize) -> Coordinate {
+    Coordinate::new(cell / BOARD_SIZE, cell % BOARD_SIZE)
+}
+
+fn encode_entry(game: &Game, value: i32) -> io::Result<[u8; ENTRY_SIZE]> {
+    let mut cells = 0u64;
+    let mut occupancy = 0u16;
+    for cell in 0..BOARD_SIZE * BOARD_SIZE {
+        let position = cell_coordinate(cell);
+        if let Some(piece) = game.board.grid[position.row][position.column] {
+            cells |= u64::from(piece_to_index(piece)) << (4 * cell);
+            occupancy |= 1 << cell;
+        }
+    }
+    let in_hand = match game.game_state.stage {
+        Stage::ChoosingPieceForOponent => None,
+        Stage::PlacingPieceGivenOponentChoice(piece) => Some(piece_to_index(piece)),
+    };
+    encode_fields(
+        (cells, occupancy),
+        in_hand,
+        game.game_state.player_turn,
+        game.game_state.result,
+        value,
+    )
+}
+
+/// Encodes the entry of `game`, without turning it into a [`Game`] first.
+pub(crate) fn encode_bit_entry(game: &BitGame, value: i32) -> io::Result<[u8; ENTRY_SIZE]> {
+    encode_fields(
+        game.packed_cells(),
+        game.in_hand(),
+        game.player_turn(),
+        game.result(),
+        value,
+    )
+}
+
+fn encode_fields(
+    (cells, occupancy): (u64, u16),
+    in_hand: Option<u8>,
+    player_turn: Player,
+    result: GameResult,
+    value: i32,
+) -> io::Result<[u8; ENTRY_SIZE]> {
+    let player = match player_turn {
+        Player::Player1 => 0,
+        Player::Player2 => 1,
+    };
+    let result = match result {
+        GameResult::InProgress => 0,
+        GameResult::PlayerWon(Player::Player1) => 1,
+        GameResult::PlayerWon(Player::Player2) => 2,
+        GameResult::Draw => 3,
+    };
+    let value = i16::try_from(value).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("value {value} doesn't fit in an entry"),
+        )
+    })?;
+
+    let mut bytes = [0; ENTRY_SIZE];
+    bytes[..8].copy_from_slice(&cells.to_le_bytes());
+    bytes[8..10].copy_from_slice(&occupancy.to_le_bytes());
+    bytes[10] = in_hand.unwrap_or(NO_PIECE);
+    bytes[11] = player | result << 1;
+    bytes[12..14].copy_from_slice(&value.to_le_bytes());
+    Ok(bytes)
+}
+
+pub(crate) fn decode_entry(bytes: &[u8]) -> io::Result<(Game, i32)> {
+    let cells = u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"));
+    let occupancy = u16::from_le_bytes([bytes[8], bytes[9]]);
+
+    let mut board = Board::new();
+    let mut pieces_left = all_pieces();
+    for cell in 0..BOARD_SIZE * BOARD_SIZE {
+        if occupancy & (1 << cell) != 0 {
+            let piece = piece_from_index((cells >> (4 * cell)) as u8 & 0xf);
+            if !pieces_left.remove(&piece) {
+                return Err(invalid_data(format!("piece {piece:?} is repeated")));
+            }
+            let position = cell_coordinate(cell);
+            board.grid[position.row][position.column] = Some(piece);
+        }
+    }
+
+    let result = match (bytes[11] >> 1) & 0b11 {
+        0 => GameResult::InProgress,
+        1 => GameResult::PlayerWon(Player::Player1),
+        2 => GameResult::PlayerWon(Player::Player2),
+        _ => GameResult::Draw,
+    };
+    let stage = match bytes[10] {
+        NO_PIECE => Stage::ChoosingPieceForOponent,
+        index if index < 1 << N_PROPERTIES => {
+            // See `Stage::PlacingPieceGivenOponentChoice` for finished games
+            let piece = piece_from_index(index);
+            if !pieces_left.remove(&piece) && result == GameResult::InProgress {
+                return Err(invalid_data(format!("piece {piece:?} is repeated")));
+            }
+            Stage::PlacingPieceGivenOponentChoice(piece)
+        }
+        index => return Err(invalid_data(format!("invalid piece {index}"))),
+    };
+    let player_turn = match bytes[11] & 1 {
+        0 => Player::Player1,
+        _ => Player::Player2,
+    };
+    let value = i16::from_le_bytes([bytes[12], bytes[13]]);
+
+    let game = Game::from_parts(
+        board,
+        GameState {
+            player_turn,
+            stage,
+            result,
+        },
+        pieces_left,
+    );
+    Ok((game, i32::from(value)))
+}
+
+/// Encodes `memory` as a database file, with `symmetries` telling how its positions were stored.
+pub(crate) fn encode(memory: &HashMap<Game, i32>, symmetries: SymmetryMode) -> io::Result<Vec<u8>> {
+    let mut entries = memory
+        .iter()
+        .map(|(game, value)| encode_entry(game, *value))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort_unstable();
+
+    let mut header = DatabaseHeader {
+        version: VERSION,
+        board_size: BOARD_SIZE as u8,
+        n_properties: N_PROPERTIES as u8,
+        rules: RuleVariant::Standard,
+        symmetries,
+        entries: entries.len() as u64,
+        checksum: 0,
+    };
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&header.encode()[..CHECKED_HEADER_SIZE]);
+    for entry in &entries {
+        hasher.update(entry);
+    }
+    header.checksum = hasher.finalize();
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + entries.len() * ENTRY_SIZE);
+    bytes.extend_from_slice(&header.encode());
+    for entry in &entries {
+        bytes.extend_from_slice(entry);
+    }
+    Ok(bytes)
+}
+
+/// Decodes a database file, checking that it describes games like the ones of this program and
+/// that it's intact.
+pub(crate) fn decode(bytes: &[u8]) -> io::Result<(DatabaseHeader, HashMap<Game, i32>)> {
+    let header = DatabaseHeader::decode(bytes)?;
+    if usize::from(header.board_size) != BOARD_SIZE
+        || usize::from(header.n_properties) != N_PROPERTIES
+    {
+        return Err(invalid_data(format!(
+            "database for a {0}x{0} board with {1} attributes per piece",
+            header.board_size, header.n_properties
+        )));
+    }
+
+    let entries = &bytes[HEADER_SIZE..];
+    if header.entries.checked_mul(ENTRY_SIZE as u64) != Some(entries.len() as u64) {
+        return Err(invalid_data(format!(
+            "expected {} entries, found {} bytes of them",
+            header.entries,
+            entries.len()
+        )));
+    }
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&bytes[..CHECKED_HEADER_SIZE]);
+    hasher.update(entries);
+    if hasher.finalize() != header.checksum {
+        return Err(invalid_data("checksum mismatch, the database is corrupt"));
+    }
+
+    let memory = entries
+        .chunks_exact(ENTRY_SIZE)
+        .map(decode_entry)
+        .collect::<io::Result<_>>()?;
+    Ok((header, memory))
+}
+
+/// Whether `bytes` start like a database file.
+pub(crate) fn is_compact(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Reads just the header of the database file at `path`.
+pub fn read_header(path: &std::path::Path) -> io::Result<DatabaseHeader> {
+    let mut bytes = [0; HEADER_SIZE];
+    io::Read::read_exact(&mut std::fs::File::open(path)?, &mut bytes)
+        .map_err(|_| invalid_data("not a Quarto database"))?;
+    DatabaseHeader::decode(&bytes)
+}
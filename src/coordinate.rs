@@ -1,5 +1,17 @@
-#[derive(Debug, Copy, Clone)]
-pub(crate) struct Coordinate {
-    pub(crate) row: usize,
-    pub(crate) column: usize,
+use serde::{Deserialize, Serialize};
+
+/// A cell of the board, indexed from the top left corner.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Coordinate {
+    /// Row, from the top.
+    pub row: usize,
+    /// Column, from the left.
+    pub column: usize,
+}
+
+impl Coordinate {
+    /// The cell at `row` and `column`, which may be outside the board.
+    pub fn new(row: usize, column: usize) -> Coordinate {
+        Coordinate { row, column }
+    }
 }
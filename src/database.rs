@@ -0,0 +1,281 @@
+use crate::bitboard::BitGame;
+use crate::compact::{self, SymmetryMode};
+use crate::game::Game;
+use crate::minimax::Minimax;
+use crate::quarto_minimax::{memory_key, QuartoMinimax};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::io::{self, prelude::*};
+use std::path::{Path, PathBuf};
+
+/// Version of the values in the JSON and bincode files. Values in version 1 only told who wins,
+/// not how soon, and files of that version had no version at all, see
+/// [`migrate_legacy_values`].
+pub(crate) const VALUES_VERSION: u16 = 2;
+
+// Start of the bincode files, followed by `VALUES_VERSION`
+const BINCODE_MAGIC: [u8; 8] = *b"QUARTOBC";
+
+/// Encodings available for the solver memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DatabaseFormat {
+    /// An object with the version and the values, keyed by the JSON encoding of every game.
+    Json,
+    /// A version followed by the bincode encoding of the whole `HashMap<Game, i32>`. It can't be
+    /// read back if `Game` changes: prefer the compact format.
+    Bincode,
+    /// The versioned format with a header described in [`DatabaseHeader`](crate::DatabaseHeader).
+    Compact,
+}
+
+impl DatabaseFormat {
+    /// Guesses the format from the file extension: `.json` for JSON, `.bin` or `.bincode` for
+    /// bincode and anything else for the compact format.
+    pub fn from_path(path: &Path) -> DatabaseFormat {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => DatabaseFormat::Json,
+            Some("bin" | "bincode") => DatabaseFormat::Bincode,
+            _ => DatabaseFormat::Compact,
+        }
+    }
+
+    /// Tells the format of an existing file from its contents.
+    pub fn detect(path: &Path) -> io::Result<DatabaseFormat> {
+        let mut start = vec![];
+        std::fs::File::open(path)?
+            .take(compact::MAGIC.len() as u64)
+            .read_to_end(&mut start)?;
+        Ok(if compact::is_compact(&start) {
+            DatabaseFormat::Compact
+        } else if start.first() == Some(&b'{') {
+            DatabaseFormat::Json
+        } else {
+            DatabaseFormat::Bincode
+        })
+    }
+}
+
+impl std::str::FromStr for DatabaseFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(DatabaseFormat::Json),
+            "bincode" | "bin" => Ok(DatabaseFormat::Bincode),
+            "compact" => Ok(DatabaseFormat::Compact),
+            _ => Err(format!("Unknown database format {s:?}")),
+        }
+    }
+}
+
+/// Reads the values of a database file written with [`write_database`] in `format`.
+pub fn read_database(path: &Path, format: DatabaseFormat) -> io::Result<HashMap<Game, i32>> {
+    match format {
+        DatabaseFormat::Json => read_from_json(path),
+        DatabaseFormat::Bincode => read_from_binary(path),
+        DatabaseFormat::Compact => Ok(compact::decode(&std::fs::read(path)?)?.1),
+    }
+}
+
+/// Writes `memory` at `path`. Only the compact format records `symmetries`, how the positions of
+/// the memory were stored.
+pub fn write_database(
+    memory: &HashMap<Game, i32>,
+    path: &Path,
+    format: DatabaseFormat,
+    symmetries: SymmetryMode,
+) -> io::Result<()> {
+    match format {
+        DatabaseFormat::Json => write_to_json(memory, path),
+        DatabaseFormat::Bincode => write_to_binary(memory, path),
+        DatabaseFormat::Compact => write_atomically(path, &compact::encode(memory, symmetries)?),
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+// Files of version 1 had no version. Their values can't be read as they are, see
+// `migrate_legacy_values`
+pub(crate) fn check_values_version(version: Option<u16>, path: &Path) -> io::Result<()> {
+    match version {
+        Some(VALUES_VERSION) => Ok(()),
+        Some(version) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "{} has version {version}, not the supported {VALUES_VERSION}",
+                path.display()
+            ),
+        )),
+        None => Err(unversioned(path)),
+    }
+}
+
+pub(crate) fn unversioned(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "{} has no version: its values only tell who wins and have to be migrated",
+            path.display()
+        ),
+    )
+}
+
+/// Whether the JSON or bincode file at `path` is of version 1, so it has to be read with
+/// [`read_legacy_database`]. Compact files never are.
+pub fn is_legacy_database(path: &Path, format: DatabaseFormat) -> io::Result<bool> {
+    match format {
+        DatabaseFormat::Json => {
+            let contents = std::fs::read_to_string(path)?;
+            let database: serde_json::Value =
+                serde_json::from_str(&contents).map_err(invalid_data)?;
+            Ok(database.get("version").is_none())
+        }
+        DatabaseFormat::Bincode => {
+            let mut start = vec![];
+            std::fs::File::open(path)?
+                .take(BINCODE_MAGIC.len() as u64)
+                .read_to_end(&mut start)?;
+            Ok(start != BINCODE_MAGIC)
+        }
+        DatabaseFormat::Compact => Ok(false),
+    }
+}
+
+/// Reads a JSON or bincode file of version 1: the games and values as they were, without a
+/// version. Its values only tell who wins, see [`migrate_legacy_values`].
+pub fn read_legacy_database(path: &Path, format: DatabaseFormat) -> io::Result<HashMap<Game, i32>> {
+    match format {
+        DatabaseFormat::Json => {
+            let contents = std::fs::read_to_string(path)?;
+            let values: HashMap<String, i32> =
+                serde_json::from_str(&contents).map_err(invalid_data)?;
+            values
+                .into_iter()
+                .map(|(key, value)| Ok((serde_json::from_str(&key).map_err(invalid_data)?, value)))
+                .collect()
+        }
+        DatabaseFormat::Bincode => {
+            bincode::deserialize(&std::fs::read(path)?).map_err(invalid_data)
+        }
+        DatabaseFormat::Compact => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "compact databases have always had a version",
+        )),
+    }
+}
+
+/// Migrates the values of a database of version 1, where a win was just 1 or -1, adding the
+/// values to the memory of `solver`. Draws are kept as they are, and wins are solved again to
+/// know how soon they happen. Fails if a win solved again isn't won by the same player.
+pub fn migrate_legacy_values(
+    solver: &mut QuartoMinimax,
+    legacy: &HashMap<Game, i32>,
+) -> io::Result<()> {
+    // Positions closer to the end first, so the others reuse their values
+    let mut positions = legacy.iter().collect::<Vec<_>>();
+    positions.sort_by_key(|(game, _)| game.get_pieces_left().len());
+    for (game, legacy_value) in positions {
+        if solver.terminal(game) {
+            continue;
+        }
+        if *legacy_value == 0 {
+            let key = memory_key(&BitGame::from(game), solver.use_symmetries);
+            solver.remember(key, 0);
+            continue;
+        }
+        let value = solver.value(game);
+        if value.signum() != legacy_value.signum() {
+            return Err(invalid_data(format!(
+                "{} was worth {legacy_value} but is worth {value}",
+                game.to_notation()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonDatabase {
+    version: u16,
+    values: HashMap<String, i32>,
+}
+
+// JSON objects only allow string keys, so every game is serialized on its own first
+fn read_from_json(path: &Path) -> io::Result<HashMap<Game, i32>> {
+    let contents = std::fs::read_to_string(path)?;
+    let database: serde_json::Value = serde_json::from_str(&contents).map_err(invalid_data)?;
+    let version = database
+        .get("version")
+        .and_then(|version| version.as_u64())
+        .map(|version| u16::try_from(version).unwrap_or(u16::MAX));
+    check_values_version(version, path)?;
+    let database: JsonDatabase = serde_json::from_value(database).map_err(invalid_data)?;
+    database
+        .values
+        .into_iter()
+        .map(|(key, value)| Ok((serde_json::from_str(&key).map_err(invalid_data)?, value)))
+        .collect()
+}
+
+fn write_to_json(memory: &HashMap<Game, i32>, path: &Path) -> io::Result<()> {
+    let values = memory
+        .iter()
+        .map(|(key, value)| Ok((serde_json::to_string(key)?, *value)))
+        .collect::<Result<HashMap<String, i32>, serde_json::Error>>()
+        .map_err(invalid_data)?;
+    let serialized = serde_json::to_string(&JsonDatabase {
+        version: VALUES_VERSION,
+        values,
+    })
+    .map_err(invalid_data)?;
+
+    write_atomically(path, serialized.as_bytes())
+}
+
+fn read_from_binary(path: &Path) -> io::Result<HashMap<Game, i32>> {
+    read_bincode(path)
+}
+
+fn write_to_binary(memory: &HashMap<Game, i32>, path: &Path) -> io::Result<()> {
+    write_bincode(memory, path)
+}
+
+// Bincode files start with `BINCODE_MAGIC` and the version, so values of another version are
+// rejected instead of misread
+pub(crate) fn read_bincode<T: DeserializeOwned>(path: &Path) -> io::Result<T> {
+    let contents = std::fs::read(path)?;
+    let header_size = BINCODE_MAGIC.len() + 2;
+    let version = match contents.get(..header_size) {
+        Some(header) if header.starts_with(&BINCODE_MAGIC) => {
+            Some(u16::from_le_bytes([header[8], header[9]]))
+        }
+        _ => None,
+    };
+    check_values_version(version, path)?;
+    bincode::deserialize(&contents[header_size..]).map_err(invalid_data)
+}
+
+pub(crate) fn write_bincode<T: Serialize>(value: &T, path: &Path) -> io::Result<()> {
+    let mut serialized = BINCODE_MAGIC.to_vec();
+    serialized.extend_from_slice(&VALUES_VERSION.to_le_bytes());
+    bincode::serialize_into(&mut serialized, value).map_err(invalid_data)?;
+    write_atomically(path, &serialized)
+}
+
+// The contents are written to a temporary file that then replaces the old one, so an interrupted
+// write leaves the old file as it was
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_path);
+
+    let mut file = std::fs::File::create(&temporary_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&temporary_path, path)
+}
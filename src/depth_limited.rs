@@ -0,0 +1,194 @@
+//! Depth-limited search: states deeper than a given number of actions are not searched but
+//! estimated by an [`Evaluator`].
+
+use crate::alpha_beta::{alpha_beta, best_action_among, Window};
+use crate::game::{Game, Player, Stage};
+use crate::minimax::{Minimax, Side};
+use crate::piece::Piece;
+use crate::BOARD_SIZE;
+
+/// Scale of the values of finished games in depth-limited searches: a game is worth its
+/// [`Minimax::utility`] times `WIN_SCORE`, from Max's perspective, so a won game is worth at least
+/// `WIN_SCORE` and a sooner win more. Evaluators have to return values strictly between
+/// `-WIN_SCORE` and `WIN_SCORE`.
+pub const WIN_SCORE: i32 = 1000;
+
+/// Estimates the value of states that aren't searched any further, from Max's perspective.
+pub trait Evaluator<State> {
+    /// The estimated value of `state`, strictly between `-WIN_SCORE` and `WIN_SCORE`.
+    fn evaluate(&self, state: &State) -> i32;
+}
+
+/// Heuristic for Quarto based on the lines that are one piece away from a win.
+///
+/// A threat is a line with three pieces sharing an attribute and an empty cell: giving a piece
+/// with that attribute lets the opponent win. Players take turns giving the safe pieces, so the
+/// one who gives the last of them leaves the opponent without any: an odd number of safe pieces
+/// is good for the player who chooses next, which is always the player to move, and an even
+/// number is bad. The fewer threats there are the more pieces will stay safe.
+#[derive(Copy, Clone, Debug)]
+pub struct QuartoEvaluator {
+    /// Value taken away for every threat, from the perspective of the player to move.
+    pub threat_weight: i32,
+    /// Value of every piece left that doesn't complete a threat, for the player to move when there
+    /// is an odd number of them and against them otherwise.
+    pub safe_piece_weight: i32,
+}
+
+impl Default for QuartoEvaluator {
+    fn default() -> Self {
+        QuartoEvaluator {
+            threat_weight: 1,
+            safe_piece_weight: 2,
+        }
+    }
+}
+
+// The value of a win that's guaranteed but hasn't happened yet
+const ALMOST_WIN: i32 = WIN_SCORE / 2;
+
+// Cells of every row, column and diagonal
+fn lines() -> impl Iterator<Item = [(usize, usize); BOARD_SIZE]> {
+    let rows = (0..BOARD_SIZE).map(|row| std::array::from_fn(|column| (row, column)));
+    let columns = (0..BOARD_SIZE).map(|column| std::array::from_fn(|row| (row, column)));
+    let diagonals = [
+        std::array::from_fn(|n| (n, n)),
+        std::array::from_fn(|n| (n, BOARD_SIZE - n - 1)),
+    ];
+    rows.chain(columns).chain(diagonals)
+}
+
+/// Attribute values that would complete a line, as `(attribute, value)` pairs.
+pub(crate) fn threats(game: &Game) -> Vec<(usize, bool)> {
+    let mut threats = vec![];
+    for line in lines() {
+        let pieces = line
+            .iter()
+            .filter_map(|(row, column)| game.board.grid[*row][*column])
+            .collect::<Vec<Piece>>();
+        if pieces.len() != BOARD_SIZE - 1 {
+            continue;
+        }
+
+        for attribute in 0..pieces[0].len() {
+            let value = pieces[0][attribute];
+            if pieces.iter().all(|piece| piece[attribute] == value) {
+                threats.push((attribute, value));
+            }
+        }
+    }
+    threats
+}
+
+fn completes_threat(piece: &Piece, threats: &[(usize, bool)]) -> bool {
+    threats
+        .iter()
+        .any(|(attribute, value)| piece[*attribute] == *value)
+}
+
+impl Evaluator<Game> for QuartoEvaluator {
+    fn evaluate(&self, state: &Game) -> i32 {
+        let threats = threats(state);
+        let pieces_left = state.get_pieces_left();
+        let safe_pieces = pieces_left
+            .iter()
+            .filter(|piece| !completes_threat(piece, &threats))
+            .count() as i32;
+
+        let for_player_to_move = match state.game_state.stage {
+            Stage::PlacingPieceGivenOponentChoice(piece) if completes_threat(&piece, &threats) => {
+                ALMOST_WIN
+            }
+            Stage::ChoosingPieceForOponent if !pieces_left.is_empty() && safe_pieces == 0 => {
+                -ALMOST_WIN
+            }
+            _ => {
+                let parity = if safe_pieces % 2 == 1 { 1 } else { -1 };
+                parity * self.safe_piece_weight * safe_pieces
+                    - self.threat_weight * threats.len() as i32
+            }
+        };
+
+        match state.game_state.player_turn {
+            Player::Player1 => for_player_to_move,
+            Player::Player2 => -for_player_to_move,
+        }
+    }
+}
+
+/// Wraps a problem so that its states carry how many more actions can be searched, and states
+/// where none are left are terminal and valued by the evaluator.
+pub struct DepthLimited<'a, M, E> {
+    /// The game searched.
+    pub problem: &'a M,
+    /// Estimates the states where the depth runs out.
+    pub evaluator: &'a E,
+}
+
+impl<'a, State, Action, M, E> Minimax<(State, u32), Action> for DepthLimited<'a, M, E>
+where
+    M: Minimax<State, Action>,
+    E: Evaluator<State>,
+{
+    fn utility(&self, (state, _): &(State, u32)) -> i32 {
+        if self.problem.terminal(state) {
+            self.problem.utility(state) * WIN_SCORE
+        } else {
+            self.evaluator.evaluate(state)
+        }
+    }
+
+    fn terminal(&self, (state, depth): &(State, u32)) -> bool {
+        *depth == 0 || self.problem.terminal(state)
+    }
+
+    fn actions(&self, (state, _): &(State, u32)) -> Vec<Action> {
+        self.problem.actions(state)
+    }
+
+    fn result(&self, (state, depth): &(State, u32), action: Action) -> (State, u32) {
+        (self.problem.result(state, action), depth - 1)
+    }
+
+    fn to_move(&self, (state, _): &(State, u32)) -> Side {
+        self.problem.to_move(state)
+    }
+}
+
+/// Value of `state` searching at most `depth` actions ahead.
+pub fn depth_limited<State, Action, M, E>(
+    problem: &M,
+    evaluator: &E,
+    state: &State,
+    depth: u32,
+    window: Window,
+) -> i32
+where
+    State: Clone,
+    M: Minimax<State, Action>,
+    E: Evaluator<State>,
+{
+    let mut limited = DepthLimited { problem, evaluator };
+    alpha_beta(&mut limited, &(state.clone(), depth), window)
+}
+
+/// The best action for the player to move searching at most `depth` actions ahead, with its
+/// value. Returns `None` if there are no actions.
+pub fn depth_limited_best_action<State, Action, M, E>(
+    problem: &M,
+    evaluator: &E,
+    state: &State,
+    depth: u32,
+) -> Option<(Action, i32)>
+where
+    State: Clone,
+    Action: Copy,
+    M: Minimax<State, Action>,
+    E: Evaluator<State>,
+{
+    let mut limited = DepthLimited { problem, evaluator };
+    // Every action gets looked at even with no depth left
+    let state = (state.clone(), depth.max(1));
+    let actions = limited.actions(&state);
+    best_action_among(&mut limited, &state, actions)
+}
@@ -0,0 +1,55 @@
+use crate::coordinate::Coordinate;
+use crate::game::GameResult;
+use crate::game::Player;
+use crate::piece::Piece;
+
+use std::fmt;
+
+/// Reasons why a move can be rejected by the engine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuartoError {
+    /// The coordinate doesn't belong to the board.
+    OutOfBounds(Coordinate),
+    /// There's already a piece at the coordinate.
+    CellOccupied(Coordinate),
+    /// There's no piece at the coordinate.
+    CellEmpty(Coordinate),
+    /// The piece is already on the board or in the opponent's hands.
+    PieceUnavailable(Piece),
+    /// The move doesn't correspond to the current stage of the turn.
+    WrongStage,
+    /// The game has already finished with the given result.
+    GameOver(GameResult),
+    /// It's not this player's turn.
+    WrongPlayer {
+        /// The player whose turn it is.
+        expected: Player,
+        /// The player trying to move.
+        actual: Player,
+    },
+}
+
+impl fmt::Display for QuartoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuartoError::OutOfBounds(position) => write!(
+                f,
+                "Position out of bounds: you requested {position:?} but board size is {}",
+                crate::BOARD_SIZE
+            ),
+            QuartoError::CellOccupied(position) => write!(f, "Place {position:?} is occupied"),
+            QuartoError::CellEmpty(position) => write!(f, "Place {position:?} is empty"),
+            QuartoError::PieceUnavailable(piece) => write!(f, "Piece {piece:?} not available"),
+            QuartoError::WrongStage => write!(f, "You can't do that at this stage of the turn"),
+            QuartoError::GameOver(GameResult::PlayerWon(player)) => {
+                write!(f, "Game is over: player {player:?} won")
+            }
+            QuartoError::GameOver(_) => write!(f, "Game is over"),
+            QuartoError::WrongPlayer { expected, actual } => {
+                write!(f, "It's {expected:?}'s turn, not {actual:?}'s")
+            }
+        }
+    }
+}
+
+impl std::error::Error for QuartoError {}
@@ -1,5 +1,6 @@
 use super::QUATRO;
 use crate::board::Board;
+use crate::error::QuartoError;
 use crate::Coordinate;
 
 use super::piece::check_match;
@@ -8,7 +9,8 @@ use super::BOARD_SIZE;
 
 use super::N_PROPERTIES;
 
-use super::piece::Piece;
+use super::piece::{piece_from_index, Piece};
+use crate::zobrist;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::hash::Hash;
@@ -20,30 +22,86 @@ pub(crate) struct GameState {
     pub(crate) result: GameResult,
 }
 
+/// Player1 is the one choosing the first piece of the game.
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) enum Player {
+pub enum Player {
+    /// Chooses the first piece.
     Player1,
+    /// Places the first piece.
     Player2,
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) enum Stage {
+impl Player {
+    /// The other player.
+    pub fn opponent(self) -> Player {
+        match self {
+            Player::Player1 => Player::Player2,
+            Player::Player2 => Player::Player1,
+        }
+    }
+}
+
+/// Every turn has two stages: the player places the piece they were given, and then chooses the
+/// piece the opponent will have to place.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stage {
+    /// The player has to give the opponent a piece.
     ChoosingPieceForOponent,
+    /// The player has to place this piece. Once the game is over, this is still the last piece
+    /// placed.
     PlacingPieceGivenOponentChoice(Piece),
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) enum GameResult {
+/// A single move: a turn is made of a `Put` followed by a `Choose`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuartoAction {
+    /// Gives the piece to the opponent.
+    Choose(Piece),
+    /// Places the piece in hand at the coordinate.
+    Put(Coordinate),
+}
+
+/// How a game stands.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameResult {
+    /// Nobody won yet and there are cells left.
     InProgress,
+    /// The player completed a line of pieces sharing an attribute.
     PlayerWon(Player),
+    /// The board is full and nobody won.
     Draw,
 }
 
+/// A game of Quarto. Moves are made by alternating [`Game::choose`] and [`Game::put`], starting
+/// with Player1 choosing a piece for Player2.
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub(crate) struct Game {
+#[serde(from = "GameData")]
+pub struct Game {
     pub(crate) board: Board<Piece>,
     pub(crate) game_state: GameState,
     pub(crate) pieces_left: HashSet<Piece>,
+    // Zobrist hash of the position, updated with every change
+    #[serde(skip)]
+    pub(crate) key: u64,
+    // Actions played so far and actions undone since the last one played, for undo and redo
+    #[serde(skip)]
+    pub(crate) history: Vec<QuartoAction>,
+    #[serde(skip)]
+    pub(crate) undone: Vec<QuartoAction>,
+}
+
+// The serialized fields of a game, the key is computed again when deserializing
+#[derive(Deserialize)]
+struct GameData {
+    board: Board<Piece>,
+    game_state: GameState,
+    pieces_left: HashSet<Piece>,
+}
+
+impl From<GameData> for Game {
+    fn from(data: GameData) -> Game {
+        Game::from_parts(data.board, data.game_state, data.pieces_left)
+    }
 }
 
 impl PartialEq for Game {
@@ -54,29 +112,99 @@ impl PartialEq for Game {
 
 impl Eq for Game {}
 
+// Equal games have equal keys, so hashing the key is enough
 impl Hash for Game {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.board.hash(state);
-        self.game_state.hash(state);
+        state.write_u64(self.key);
     }
 }
 
-impl Game {
-    pub(crate) fn new() -> Game {
-        let mut pieces = HashSet::<Piece>::new();
-        for piece in all_possible_pieces(N_PROPERTIES) {
-            pieces.insert(piece.try_into().unwrap()); // TODO: may panic
-        }
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
+}
 
-        Game {
-            board: Board::new(),
-            game_state: GameState {
+impl Game {
+    /// The empty board, with Player1 choosing the first piece.
+    pub fn new() -> Game {
+        Game::from_parts(
+            Board::new(),
+            GameState {
                 player_turn: Player::Player1,
                 stage: Stage::ChoosingPieceForOponent,
                 result: GameResult::InProgress,
             },
-            pieces_left: pieces,
+            all_pieces(),
+        )
+    }
+
+    /// A game in the given position, with no history.
+    pub(crate) fn from_parts(
+        board: Board<Piece>,
+        game_state: GameState,
+        pieces_left: HashSet<Piece>,
+    ) -> Game {
+        let mut game = Game {
+            board,
+            game_state,
+            pieces_left,
+            key: 0,
+            history: vec![],
+            undone: vec![],
+        };
+        game.key = zobrist::hash(&game);
+        game
+    }
+
+    /// A copy of the position, without the undo and redo history.
+    pub(crate) fn position(&self) -> Game {
+        Game {
+            board: self.board.clone(),
+            game_state: self.game_state.clone(),
+            pieces_left: self.pieces_left.clone(),
+            key: self.key,
+            history: vec![],
+            undone: vec![],
+        }
+    }
+
+    /// Zobrist hash of the position: the pieces on the board, the piece waiting to be placed and
+    /// the player to move. Equal positions have equal keys, and different positions almost
+    /// always have different ones.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// The pieces placed so far.
+    pub fn board(&self) -> &Board<Piece> {
+        &self.board
+    }
+
+    /// The player that has to make the next move.
+    pub fn player_turn(&self) -> Player {
+        self.game_state.player_turn
+    }
+
+    /// Whether a piece has to be chosen or placed, and which one.
+    pub fn stage(&self) -> &Stage {
+        &self.game_state.stage
+    }
+
+    /// Who won, if the game is over.
+    pub fn result(&self) -> &GameResult {
+        &self.game_state.result
+    }
+
+    /// Fails unless it's `player`'s turn, so front-ends can validate who is trying to move.
+    pub fn check_turn(&self, player: Player) -> Result<(), QuartoError> {
+        if self.game_state.player_turn != player {
+            return Err(QuartoError::WrongPlayer {
+                expected: self.game_state.player_turn,
+                actual: player,
+            });
         }
+        Ok(())
     }
 
     pub(crate) fn check_row_match(&self, row: usize) -> bool {
@@ -114,40 +242,133 @@ impl Game {
         forward_slash_diagonal.len() == QUATRO && check_match(forward_slash_diagonal)
     }
 
-    pub(crate) fn get_pieces_left(&self) -> Vec<Piece> {
+    /// Pieces that are neither on the board nor waiting to be placed.
+    pub fn get_pieces_left(&self) -> Vec<Piece> {
         self.pieces_left.iter().cloned().collect()
     }
 
-    pub(crate) fn get_empty_places(&self) -> Vec<Coordinate> {
+    /// Cells without a piece, row by row.
+    pub fn get_empty_places(&self) -> Vec<Coordinate> {
         self.board.empty_spaces()
     }
 
-    pub(crate) fn choose(&mut self, piece: Piece) -> Result<(), String> {
-        // TODO: add player as parameter and check
+    /// Every action the player to move can play, none once the game is over.
+    pub fn legal_actions(&self) -> Vec<QuartoAction> {
+        if self.game_state.result != GameResult::InProgress {
+            return vec![];
+        }
+        match self.game_state.stage {
+            // Sorted so the order doesn't change from run to run
+            Stage::ChoosingPieceForOponent => {
+                let mut pieces = self.get_pieces_left();
+                pieces.sort();
+                pieces.into_iter().map(QuartoAction::Choose).collect()
+            }
+            Stage::PlacingPieceGivenOponentChoice(_) => self
+                .get_empty_places()
+                .into_iter()
+                .map(QuartoAction::Put)
+                .collect(),
+        }
+    }
+
+    /// Actions played so far, oldest first.
+    pub fn history(&self) -> &[QuartoAction] {
+        &self.history
+    }
 
-        if !self.get_pieces_left().contains(&piece) {
-            // TODO: this may not work due to reference
-            return Err("Piece not available".to_string());
+    /// Plays either kind of action. Playing discards the actions that could be redone.
+    pub fn play(&mut self, action: QuartoAction) -> Result<(), QuartoError> {
+        self.apply(action)?;
+        self.undone.clear();
+        Ok(())
+    }
+
+    fn apply(&mut self, action: QuartoAction) -> Result<(), QuartoError> {
+        match action {
+            QuartoAction::Choose(piece) => self.choose_piece(piece)?,
+            QuartoAction::Put(position) => self.put_piece(position)?,
         }
+        self.history.push(action);
+        Ok(())
+    }
 
-        match self.game_state.stage {
-            Stage::PlacingPieceGivenOponentChoice(_) => {
-                Err("You can't place a piece right now".to_string())
+    /// Reverts the last action played, returning it.
+    pub fn undo(&mut self) -> Option<QuartoAction> {
+        let action = self.history.pop()?;
+        match action {
+            QuartoAction::Choose(piece) => {
+                self.pieces_left.insert(piece);
+                self.set_stage(Stage::ChoosingPieceForOponent);
+                self.switch_player();
+            }
+            QuartoAction::Put(position) => {
+                let piece = self
+                    .board
+                    .remove(position)
+                    .expect("the piece was placed by the action being undone");
+                self.key ^= zobrist::cell_key(position, piece);
+                self.set_stage(Stage::PlacingPieceGivenOponentChoice(piece));
+                self.game_state.result = GameResult::InProgress;
             }
+        }
+        self.undone.push(action);
+        Some(action)
+    }
+
+    /// Plays again the last action undone, returning it.
+    pub fn redo(&mut self) -> Option<QuartoAction> {
+        let action = self.undone.pop()?;
+        self.apply(action)
+            .expect("undone actions were legal in this same position");
+        Some(action)
+    }
+
+    /// Gives `piece` to the opponent, who will have to place it next.
+    pub fn choose(&mut self, piece: Piece) -> Result<(), QuartoError> {
+        self.play(QuartoAction::Choose(piece))
+    }
+
+    /// Places the piece chosen by the opponent at `position`.
+    pub fn put(&mut self, position: Coordinate) -> Result<(), QuartoError> {
+        self.play(QuartoAction::Put(position))
+    }
+
+    // The stage and the player are only changed through these, to keep the key up to date
+    fn set_stage(&mut self, stage: Stage) {
+        self.key ^= zobrist::stage_key(self.game_state.stage) ^ zobrist::stage_key(stage);
+        self.game_state.stage = stage;
+    }
+
+    fn switch_player(&mut self) {
+        let player = self.game_state.player_turn;
+        self.key ^= zobrist::player_key(player) ^ zobrist::player_key(player.opponent());
+        self.game_state.player_turn = player.opponent();
+    }
+
+    fn choose_piece(&mut self, piece: Piece) -> Result<(), QuartoError> {
+        if self.game_state.result != GameResult::InProgress {
+            return Err(QuartoError::GameOver(self.game_state.result));
+        }
+
+        if !self.pieces_left.contains(&piece) {
+            return Err(QuartoError::PieceUnavailable(piece));
+        }
+
+        match self.game_state.stage {
+            Stage::PlacingPieceGivenOponentChoice(_) => Err(QuartoError::WrongStage),
             Stage::ChoosingPieceForOponent => {
-                self.pieces_left.remove(&piece); // TODO: this may not work due to reference
+                self.pieces_left.remove(&piece);
 
-                self.game_state.stage = Stage::PlacingPieceGivenOponentChoice(piece);
-                self.game_state.player_turn = match self.game_state.player_turn {
-                    Player::Player1 => Player::Player2,
-                    Player::Player2 => Player::Player1,
-                };
+                self.set_stage(Stage::PlacingPieceGivenOponentChoice(piece));
+                self.switch_player();
                 Ok(())
             }
         }
     }
 
-    pub(crate) fn check_if_won(&self, position: Coordinate) -> bool {
+    /// Whether any line going through `position` is full of pieces sharing an attribute.
+    pub fn check_if_won(&self, position: Coordinate) -> bool {
         if self.check_row_match(position.row) {
             return true;
         }
@@ -167,25 +388,24 @@ impl Game {
         false
     }
 
-    pub(crate) fn put(&mut self, position: Coordinate) -> Result<(), String> {
-        // TODO: add player as parameter and check
-        // TODO: reduce reading complexity
+    fn put_piece(&mut self, position: Coordinate) -> Result<(), QuartoError> {
         match self.game_state.result {
-            GameResult::Draw => Err("Game is over".to_string()),
-            GameResult::PlayerWon(player) => Err(format!("Player {:?} won", player)),
+            GameResult::Draw | GameResult::PlayerWon(_) => {
+                Err(QuartoError::GameOver(self.game_state.result))
+            }
             GameResult::InProgress => match self.game_state.stage {
-                Stage::ChoosingPieceForOponent => {
-                    Err("You can't place a piece right now".to_string())
-                }
+                Stage::ChoosingPieceForOponent => Err(QuartoError::WrongStage),
                 Stage::PlacingPieceGivenOponentChoice(piece) => {
-                    self.board.put(piece, position)?; // TODO: check if this changes the result
+                    self.board.put(piece, position)?;
+                    self.key ^= zobrist::cell_key(position, piece);
 
-                    if self.pieces_left.is_empty() {
-                        self.game_state.result = GameResult::Draw;
-                    } else if self.check_if_won(position) {
+                    // The last piece can still win the game
+                    if self.check_if_won(position) {
                         self.game_state.result = GameResult::PlayerWon(self.game_state.player_turn);
+                    } else if self.pieces_left.is_empty() {
+                        self.game_state.result = GameResult::Draw;
                     } else {
-                        self.game_state.stage = Stage::ChoosingPieceForOponent;
+                        self.set_stage(Stage::ChoosingPieceForOponent);
                     }
 
                     Ok(())
@@ -195,19 +415,6 @@ impl Game {
     }
 }
 
-fn all_possible_pieces(size: usize) -> Vec<Vec<bool>> {
-    if size == 0 {
-        return vec![vec![]];
-    }
-    let smaller_pieces = all_possible_pieces(size - 1);
-    let mut pieces = vec![];
-    for piece in smaller_pieces {
-        let mut new_piece = piece.clone();
-        new_piece.push(false);
-        pieces.push(new_piece);
-        let mut new_piece = piece.clone();
-        new_piece.push(true);
-        pieces.push(new_piece);
-    }
-    pieces
+pub(crate) fn all_pieces() -> HashSet<Piece> {
+    (0..1 << N_PROPERTIES).map(piece_from_index).collect()
 }
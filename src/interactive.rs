@@ -0,0 +1,187 @@
+//! Games on the terminal, between humans, the computer or both.
+
+use crate::depth_limited::{depth_limited_best_action, QuartoEvaluator};
+use crate::game::{Game, GameResult, Player, QuartoAction, Stage};
+use crate::iterative_deepening::iterative_deepening;
+use crate::mcts::{Limits, Mcts};
+use crate::notation::format_piece;
+use crate::quarto_minimax::QuartoMinimax;
+use crate::record::{format_action, parse_action};
+
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+/// Who makes the moves for a player in an interactive game.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Controller {
+    /// Moves are read from the terminal.
+    Human,
+    /// Moves are searched with the configured [`Search`].
+    Computer,
+}
+
+/// How the computer searches for its moves.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Search {
+    /// Solve the position, which is only feasible towards the end of the game.
+    Exhaustive,
+    /// Look this many actions ahead and estimate the rest with [`QuartoEvaluator`].
+    DepthLimited(u32),
+    /// Look as many actions ahead as possible within this time, see [`iterative_deepening`].
+    TimeLimited(Duration),
+    /// Monte Carlo tree search, see [`Mcts`].
+    Mcts {
+        /// When to stop searching each move.
+        limits: Limits,
+        /// Seed of the random actions, so games can be replayed.
+        seed: u64,
+    },
+}
+
+enum Command {
+    Play(QuartoAction),
+    Undo,
+    Redo,
+}
+
+/// Plays a whole game on the terminal, reading the human moves from `input` and writing the board
+/// and prompts to `output`. Humans can also type `undo` and `redo` to go back to their previous
+/// decision or forward again. Computer moves are searched as given by `search`, with `qmm`.
+/// Returns the result, or `None` if `input` ran out before the end.
+pub fn play<R: BufRead, W: Write>(
+    players: [Controller; 2],
+    search: Search,
+    qmm: &mut QuartoMinimax,
+    mut input: R,
+    mut output: W,
+) -> io::Result<Option<GameResult>> {
+    let mut game = Game::new();
+    // Kept between moves to reuse the tree
+    let mut mcts: Option<Mcts> = None;
+    let controller = |player| match player {
+        Player::Player1 => players[0],
+        Player::Player2 => players[1],
+    };
+
+    while *game.result() == GameResult::InProgress {
+        writeln!(output, "\n{}\n", game.board())?;
+
+        let player = game.player_turn();
+        let command = match controller(player) {
+            Controller::Computer => {
+                let action = match search {
+                    Search::Exhaustive => qmm.best_action(&game).map(|(action, _)| action).ok(),
+                    Search::DepthLimited(depth) => {
+                        depth_limited_best_action(qmm, &QuartoEvaluator::default(), &game, depth)
+                            .map(|(action, _)| action)
+                    }
+                    Search::TimeLimited(budget) => {
+                        let found =
+                            iterative_deepening(qmm, &QuartoEvaluator::default(), &game, budget);
+                        if let Some((_, _, depth)) = found {
+                            writeln!(output, "{player:?} looked {depth} actions ahead")?;
+                        }
+                        found.map(|(action, _, _)| action)
+                    }
+                    Search::Mcts { limits, seed } => {
+                        let mcts = mcts.get_or_insert_with(|| Mcts::new(&game, seed));
+                        mcts.follow(&game);
+                        let found = mcts.search(limits);
+                        if let Some((_, score)) = found {
+                            let playouts = mcts.playouts();
+                            writeln!(
+                                output,
+                                "{player:?} expects to score {score:.2} after {playouts} playouts"
+                            )?;
+                        }
+                        found.map(|(action, _)| action)
+                    }
+                }
+                .expect("the game is in progress");
+                writeln!(output, "{player:?} plays {}", format_action(action))?;
+                Command::Play(action)
+            }
+            Controller::Human => match ask_command(&game, &mut input, &mut output)? {
+                Some(command) => command,
+                None => return Ok(None),
+            },
+        };
+
+        match command {
+            Command::Play(action) => {
+                if let Err(error) = game.play(action) {
+                    writeln!(output, "{error}")?;
+                }
+            }
+            // Computer actions are skipped over, otherwise it'd just play them again
+            Command::Undo => {
+                while game.undo().is_some() {
+                    if controller(game.player_turn()) == Controller::Human {
+                        break;
+                    }
+                }
+            }
+            Command::Redo => {
+                while game.redo().is_some() {
+                    if controller(game.player_turn()) == Controller::Human {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    writeln!(output, "\n{}\n", game.board())?;
+    match game.result() {
+        GameResult::PlayerWon(player) => writeln!(output, "{player:?} won!")?,
+        _ => writeln!(output, "It's a draw!")?,
+    }
+
+    Ok(Some(*game.result()))
+}
+
+fn ask_command<R: BufRead, W: Write>(
+    game: &Game,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<Option<Command>> {
+    let player = game.player_turn();
+    loop {
+        match game.stage() {
+            Stage::ChoosingPieceForOponent => {
+                let pieces = game
+                    .legal_actions()
+                    .into_iter()
+                    .map(format_action)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(output, "Pieces left: {pieces}")?;
+                write!(output, "{player:?}, choose a piece for your opponent: ")?;
+            }
+            Stage::PlacingPieceGivenOponentChoice(piece) => {
+                write!(
+                    output,
+                    "{player:?}, place {} (column and row, e.g. a1): ",
+                    format_piece(*piece)
+                )?;
+            }
+        }
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        // The same syntax as game records: a hex digit for a piece, e.g. a1 for a cell
+        let action = match line.trim() {
+            "undo" => Some(Command::Undo),
+            "redo" => Some(Command::Redo),
+            token => parse_action(token).ok().map(Command::Play),
+        };
+        match action {
+            Some(command) => return Ok(Some(command)),
+            None => writeln!(output, "Couldn't understand {:?}", line.trim())?,
+        }
+    }
+}
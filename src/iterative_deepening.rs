@@ -0,0 +1,148 @@
+//! Depth-limited searches one action deeper at a time until a time budget runs out, so the
+//! computer can answer in bounded time however far it is from the end of the game.
+
+use crate::alpha_beta::{best_action_among, Window};
+use crate::depth_limited::{DepthLimited, Evaluator, WIN_SCORE};
+use crate::minimax::{Minimax, Side};
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+// Values are remembered for states with at least this many actions left to search. Deeper states
+// are too many to keep and don't gain much from ordering their actions
+const MIN_REMEMBERED_DEPTH: u32 = 2;
+
+/// A depth-limited search that remembers the values found in the previous iterations to try the
+/// most promising actions first, and stops searching when the deadline passes.
+struct Deepening<'a, State, M, E> {
+    limited: DepthLimited<'a, M, E>,
+    values: HashMap<State, i32>,
+    deadline: Option<Instant>,
+    timed_out: Cell<bool>,
+    // Whether some state was valued by the evaluator rather than by the end of the game
+    cut_off: Cell<bool>,
+}
+
+impl<'a, State, Action, M, E> Minimax<(State, u32), Action> for Deepening<'a, State, M, E>
+where
+    State: Clone + Eq + Hash,
+    Action: Copy,
+    M: Minimax<State, Action>,
+    E: Evaluator<State>,
+{
+    fn utility(&self, state: &(State, u32)) -> i32 {
+        self.limited.utility(state)
+    }
+
+    // Once time is out every state is terminal, which unwinds the search as fast as possible.
+    // The values found from then on are meaningless, the iteration is thrown away
+    fn terminal(&self, state: &(State, u32)) -> bool {
+        if self.limited.problem.terminal(&state.0) {
+            return true;
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.timed_out.set(true);
+        }
+        if state.1 == 0 {
+            self.cut_off.set(true);
+        }
+        state.1 == 0 || self.timed_out.get()
+    }
+
+    fn actions(&self, state: &(State, u32)) -> Vec<Action> {
+        let mut actions = self.limited.actions(state);
+        if !self.values.contains_key(&state.0) {
+            return actions;
+        }
+
+        // Actions leading to states with no value yet go last
+        let side = self.to_move(state);
+        actions.sort_by_cached_key(|action| {
+            let next_state = self.limited.problem.result(&state.0, *action);
+            match (side, self.values.get(&next_state)) {
+                (_, None) => i32::MAX,
+                (Side::Max, Some(value)) => -value,
+                (Side::Min, Some(value)) => *value,
+            }
+        });
+        actions
+    }
+
+    fn result(&self, state: &(State, u32), action: Action) -> (State, u32) {
+        self.limited.result(state, action)
+    }
+
+    fn to_move(&self, state: &(State, u32)) -> Side {
+        self.limited.to_move(state)
+    }
+
+    // Values are only used to order actions, so bounds are good enough
+    fn memoized_within<F>(&mut self, state: &(State, u32), _window: Window, search: F) -> i32
+    where
+        F: FnOnce(&mut Self) -> i32,
+    {
+        let value = search(self);
+        if state.1 >= MIN_REMEMBERED_DEPTH && !self.timed_out.get() {
+            self.values.insert(state.0.clone(), value);
+        }
+        value
+    }
+}
+
+/// The best action for the player to move found within `budget`, with its value and the number
+/// of actions searched ahead. Searches 1 action ahead, then 2, and so on until time runs out,
+/// keeping the result of the last search that finished. The first search always finishes, so an
+/// action is returned even with no budget. Returns `None` if there are no actions.
+pub fn iterative_deepening<State, Action, M, E>(
+    problem: &M,
+    evaluator: &E,
+    state: &State,
+    budget: Duration,
+) -> Option<(Action, i32, u32)>
+where
+    State: Clone + Eq + Hash,
+    Action: Copy + PartialEq,
+    M: Minimax<State, Action>,
+    E: Evaluator<State>,
+{
+    let deadline = Instant::now() + budget;
+    let mut search = Deepening {
+        limited: DepthLimited { problem, evaluator },
+        values: HashMap::new(),
+        deadline: None,
+        timed_out: Cell::new(false),
+        cut_off: Cell::new(false),
+    };
+
+    let mut actions = problem.actions(state);
+    let mut best = None;
+    for depth in 1.. {
+        search.cut_off.set(false);
+        let found = best_action_among(&mut search, &(state.clone(), depth), actions.clone());
+        if search.timed_out.get() {
+            break;
+        }
+
+        let (action, value) = found?;
+        best = Some((action, value, depth));
+
+        // The search reached the end of the game everywhere, going deeper won't change anything
+        if !search.cut_off.get() || value.abs() >= WIN_SCORE {
+            break;
+        }
+
+        // The best action so far is searched first, the sooner a good value is found the more
+        // the search of the rest can be cut short
+        if let Some(index) = actions.iter().position(|candidate| *candidate == action) {
+            actions[..=index].rotate_right(1);
+        }
+        search.deadline = Some(deadline);
+    }
+
+    best
+}
@@ -0,0 +1,232 @@
+//! Append-only log of the values found by the solver, so they are on disk a moment after being
+//! found instead of only when the whole memo is saved.
+//!
+//! The log starts with the magic `QUARTOLG` and the version of the log as a little endian `u16`.
+//! Every record is a state and its value, encoded as an entry of the compact database files,
+//! followed by the CRC-32 of the entry as a little endian `u32`. A process killed while writing
+//! leaves a truncated or corrupt last record, which is dropped when the log is opened again.
+//!
+//! Logs of version 2 have the same values, in records of the length of their payload and its
+//! CRC-32 followed by the bincode encoding of a `(Game, i32)` pair. They are rewritten in the
+//! current version when opened.
+
+use crate::bitboard::BitGame;
+use crate::compact::{self, ENTRY_SIZE};
+use crate::database::{unversioned, write_atomically};
+use crate::game::Game;
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// How long values can stay in memory before being written to the log
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+// Records are written together once this many bytes of them are waiting
+const BUFFER_SIZE: usize = 1 << 16;
+// Records appended between looks at the clock, which is slower than appending one
+const RECORDS_PER_CLOCK_CHECK: u32 = 256;
+
+const MAGIC: [u8; 8] = *b"QUARTOLG";
+const FILE_HEADER_SIZE: usize = MAGIC.len() + 2;
+// Version of the log format. Its values are the ones of `VALUES_VERSION`
+const VERSION: u16 = 3;
+// The version whose records are bincode encoded `(Game, i32)` pairs of any length
+const BINCODE_VERSION: u16 = 2;
+const RECORD_SIZE: usize = ENTRY_SIZE + 4;
+// Header of every record of `BINCODE_VERSION`
+const BINCODE_HEADER_SIZE: usize = 8;
+
+/// An open log that values are appended to.
+pub struct Journal {
+    writer: BufWriter<File>,
+    last_sync: Instant,
+    // Records appended since the clock was last checked
+    unchecked: u32,
+    // The first error appending, reported by the next sync
+    error: Option<io::Error>,
+}
+
+impl Journal {
+    /// Opens the log at `path`, creating it if it doesn't exist, and returns it with the values
+    /// already in it. Anything after the last valid record is removed.
+    pub fn open(path: &Path) -> io::Result<(Journal, HashMap<Game, i32>)> {
+        let contents = match std::fs::read(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => vec![],
+            Err(error) => return Err(error),
+        };
+        let (memory, mut valid_length, version) = read_records(&contents, path)?;
+        if version == BINCODE_VERSION {
+            let mut rewritten = file_header().to_vec();
+            for (game, value) in &memory {
+                write_record(&mut rewritten, &BitGame::from(game), *value)?;
+            }
+            write_atomically(path, &rewritten)?;
+            valid_length = rewritten.len();
+        }
+
+        let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+        if valid_length < file.metadata()?.len() as usize {
+            file.set_len(valid_length as u64)?;
+        }
+        // A new log, or one whose header was being written
+        if valid_length < FILE_HEADER_SIZE {
+            file.write_all(&file_header())?;
+            file.sync_data()?;
+        }
+
+        let journal = Journal {
+            writer: BufWriter::with_capacity(BUFFER_SIZE, file),
+            last_sync: Instant::now(),
+            unchecked: 0,
+            error: None,
+        };
+        Ok((journal, memory))
+    }
+
+    /// The values in the log at `path`, without opening it for writing. A missing log has none.
+    pub fn read(path: &Path) -> io::Result<HashMap<Game, i32>> {
+        match std::fs::read(path) {
+            Ok(contents) => Ok(read_records(&contents, path)?.0),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Adds a value to the log. It's written within a second, an error doing it is returned by
+    /// the next [`Journal::sync`].
+    pub fn append(&mut self, game: &Game, value: i32) {
+        self.append_state(&BitGame::from(game), value);
+    }
+
+    /// Like [`Journal::append`], for the states the solver searches.
+    pub(crate) fn append_state(&mut self, state: &BitGame, value: i32) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let result = write_record(&mut self.writer, state, value).and_then(|()| {
+            self.unchecked += 1;
+            if self.unchecked < RECORDS_PER_CLOCK_CHECK {
+                return Ok(());
+            }
+            self.unchecked = 0;
+            if self.last_sync.elapsed() >= SYNC_INTERVAL {
+                self.sync_to_disk()
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
+
+    /// Writes every value appended so far to disk.
+    pub fn sync(&mut self) -> io::Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.sync_to_disk()
+    }
+
+    /// Empties the log, once its values are saved somewhere else.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.sync()?;
+        self.writer.get_ref().set_len(FILE_HEADER_SIZE as u64)
+    }
+
+    fn sync_to_disk(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+}
+
+fn write_record<W: Write>(writer: &mut W, state: &BitGame, value: i32) -> io::Result<()> {
+    let entry = compact::encode_bit_entry(state, value)?;
+    let mut record = [0; RECORD_SIZE];
+    record[..ENTRY_SIZE].copy_from_slice(&entry);
+    record[ENTRY_SIZE..].copy_from_slice(&crc32fast::hash(&entry).to_le_bytes());
+    writer.write_all(&record)
+}
+
+fn file_header() -> [u8; FILE_HEADER_SIZE] {
+    let mut header = [0; FILE_HEADER_SIZE];
+    header[..MAGIC.len()].copy_from_slice(&MAGIC);
+    header[MAGIC.len()..].copy_from_slice(&VERSION.to_le_bytes());
+    header
+}
+
+// The values of the records up to the first invalid one, where that one starts and the version of
+// the log. A log without the header is from before values had a version, and its values can't be
+// used
+fn read_records(contents: &[u8], path: &Path) -> io::Result<(HashMap<Game, i32>, usize, u16)> {
+    if contents.len() < FILE_HEADER_SIZE && file_header().starts_with(contents) {
+        return Ok((HashMap::new(), 0, VERSION));
+    }
+    let version = match contents.get(..FILE_HEADER_SIZE) {
+        Some(header) if header.starts_with(&MAGIC) => u16::from_le_bytes([header[8], header[9]]),
+        _ => return Err(unversioned(path)),
+    };
+    let records = &contents[FILE_HEADER_SIZE..];
+    let (memory, length) = match version {
+        VERSION => read_entries(records),
+        BINCODE_VERSION => read_bincode_records(records),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "{} has version {version}, not the supported {VERSION}",
+                    path.display()
+                ),
+            ))
+        }
+    };
+    Ok((memory, FILE_HEADER_SIZE + length, version))
+}
+
+fn read_entries(records: &[u8]) -> (HashMap<Game, i32>, usize) {
+    let mut memory = HashMap::new();
+    let mut position = 0;
+    for record in records.chunks_exact(RECORD_SIZE) {
+        let (entry, checksum) = record.split_at(ENTRY_SIZE);
+        if crc32fast::hash(entry).to_le_bytes() != checksum {
+            break;
+        }
+        let Ok((game, value)) = compact::decode_entry(entry) else {
+            break;
+        };
+
+        memory.insert(game, value);
+        position += RECORD_SIZE;
+    }
+    (memory, position)
+}
+
+fn read_bincode_records(records: &[u8]) -> (HashMap<Game, i32>, usize) {
+    let mut memory = HashMap::new();
+    let mut position = 0;
+    while let Some(header) = records.get(position..position + BINCODE_HEADER_SIZE) {
+        let length = u32::from_le_bytes(header[..4].try_into().expect("4 bytes")) as usize;
+        let checksum = u32::from_le_bytes(header[4..].try_into().expect("4 bytes"));
+        let start = position + BINCODE_HEADER_SIZE;
+        let Some(payload) = records.get(start..start + length) else {
+            break;
+        };
+        if crc32fast::hash(payload) != checksum {
+            break;
+        }
+        let Ok((game, value)) = bincode::deserialize::<(Game, i32)>(payload) else {
+            break;
+        };
+
+        memory.insert(game, value);
+        position = start + length;
+    }
+    (memory, position)
+}
@@ -1,5 +1,9 @@
-use quatro_in_rust::themain;
+use quatro_in_rust::cli;
 
 pub fn main() {
-    themain().unwrap();
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Err(error) = cli::run(&args) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
 }
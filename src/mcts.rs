@@ -0,0 +1,212 @@
+//! Monte Carlo tree search: instead of searching every action, the most promising ones are
+//! estimated by finishing the game with random actions many times. Unlike minimax it gives a
+//! reasonable move from the very first one.
+
+use crate::error::QuartoError;
+use crate::game::{Game, GameResult, Player, QuartoAction};
+use crate::random::SplitMix64;
+
+use std::time::{Duration, Instant};
+
+/// When to stop searching. The search stops at whichever limit comes first, and always runs at
+/// least one iteration.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Stop after this many iterations.
+    pub iterations: Option<u64>,
+    /// Stop after this much time.
+    pub time: Option<Duration>,
+}
+
+/// How much exploring less visited actions is favoured over the ones that did well so far.
+pub const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+struct Node {
+    // The action leading to this node from its parent and the player that played it
+    action: Option<QuartoAction>,
+    player: Option<Player>,
+    visits: u32,
+    // Sum of the scores for `player`: 1 for a win, 0.5 for a draw and 0 for a loss
+    score: f64,
+    children: Vec<Node>,
+    untried: Vec<QuartoAction>,
+}
+
+impl Node {
+    fn new(action: Option<QuartoAction>, player: Option<Player>, state: &Game) -> Node {
+        Node {
+            action,
+            player,
+            visits: 0,
+            score: 0.0,
+            children: vec![],
+            untried: state.legal_actions(),
+        }
+    }
+
+    fn update(&mut self, result: GameResult) {
+        self.visits += 1;
+        self.score += match (result, self.player) {
+            (GameResult::PlayerWon(winner), Some(player)) if winner == player => 1.0,
+            (GameResult::Draw, _) => 0.5,
+            _ => 0.0,
+        };
+    }
+
+    // Upper confidence bound of the score of the child, as seen by the parent
+    fn uct(&self, parent_visits: u32, exploration: f64) -> f64 {
+        let visits = f64::from(self.visits);
+        self.score / visits + exploration * (f64::from(parent_visits).ln() / visits).sqrt()
+    }
+}
+
+/// A Monte Carlo tree search (UCT) player. The tree is kept between moves: after following the
+/// actions actually played, the part of the tree below them is reused.
+pub struct Mcts {
+    state: Game,
+    root: Node,
+    rng: SplitMix64,
+    exploration: f64,
+}
+
+impl Mcts {
+    /// Creates a player for `state`. The same seed gives the same moves for the same limits on
+    /// iterations.
+    pub fn new(state: &Game, seed: u64) -> Mcts {
+        Mcts {
+            state: state.clone(),
+            root: Node::new(None, None, state),
+            rng: SplitMix64::new(seed),
+            exploration: DEFAULT_EXPLORATION,
+        }
+    }
+
+    /// Replaces [`DEFAULT_EXPLORATION`].
+    pub fn set_exploration(&mut self, exploration: f64) {
+        self.exploration = exploration;
+    }
+
+    /// The state the player is searching from.
+    pub fn state(&self) -> &Game {
+        &self.state
+    }
+
+    /// Number of playouts made from the current state, including the ones reused from earlier
+    /// searches.
+    pub fn playouts(&self) -> u32 {
+        self.root.visits
+    }
+
+    /// Moves the root of the tree to the state reached by playing `action`, keeping what was
+    /// learned about it.
+    pub fn advance(&mut self, action: QuartoAction) -> Result<(), QuartoError> {
+        let player = self.state.player_turn();
+        self.state.play(action)?;
+        self.root = match self
+            .root
+            .children
+            .iter()
+            .position(|child| child.action == Some(action))
+        {
+            Some(index) => self.root.children.swap_remove(index),
+            None => Node::new(Some(action), Some(player), &self.state),
+        };
+        Ok(())
+    }
+
+    /// Catches up with `game`: if `game` continues the current state the tree is advanced through
+    /// the actions played since, otherwise the search starts over from `game`.
+    pub fn follow(&mut self, game: &Game) {
+        if let Some(actions) = game.history().strip_prefix(self.state.history()) {
+            let actions = actions.to_vec();
+            let advanced = actions
+                .into_iter()
+                .all(|action| self.advance(action).is_ok());
+            if advanced && self.state == *game {
+                return;
+            }
+        }
+
+        self.state = game.clone();
+        self.root = Node::new(None, None, game);
+    }
+
+    /// Searches until a limit is reached, and returns the most visited action with the expected
+    /// score of the player to move: 1 for a sure win, 0.5 for a draw and 0 for a sure loss.
+    /// Returns `None` if the game is over.
+    pub fn search(&mut self, limits: Limits) -> Option<(QuartoAction, f64)> {
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            let mut state = self.state.clone();
+            iterate(&mut self.root, &mut state, &mut self.rng, self.exploration);
+            iterations += 1;
+
+            let out_of_iterations = limits.iterations.is_some_and(|limit| iterations >= limit);
+            let out_of_time = limits.time.is_some_and(|limit| start.elapsed() >= limit);
+            if out_of_iterations || out_of_time || limits == Limits::default() {
+                break;
+            }
+        }
+
+        self.root
+            .children
+            .iter()
+            .max_by_key(|child| child.visits)
+            .map(|child| {
+                (
+                    child.action.expect("children have actions"),
+                    child.score / f64::from(child.visits),
+                )
+            })
+    }
+}
+
+// Selection, expansion, simulation and backpropagation, from `node` in `state`. Returns the
+// result of the game played
+fn iterate(
+    node: &mut Node,
+    state: &mut Game,
+    rng: &mut SplitMix64,
+    exploration: f64,
+) -> GameResult {
+    let result = if *state.result() != GameResult::InProgress {
+        *state.result()
+    } else if !node.untried.is_empty() {
+        let action = node.untried.swap_remove(rng.below(node.untried.len()));
+        let player = state.player_turn();
+        state.play(action).expect("untried actions are legal");
+        let mut child = Node::new(Some(action), Some(player), state);
+        let result = playout(state, rng);
+        child.update(result);
+        node.children.push(child);
+        result
+    } else {
+        let parent_visits = node.visits;
+        let child = node
+            .children
+            .iter_mut()
+            .max_by(|a, b| {
+                a.uct(parent_visits, exploration)
+                    .total_cmp(&b.uct(parent_visits, exploration))
+            })
+            .expect("a game in progress has actions");
+        state
+            .play(child.action.expect("children have actions"))
+            .expect("children actions are legal");
+        iterate(child, state, rng, exploration)
+    };
+
+    node.update(result);
+    result
+}
+
+// Finishes the game with random actions
+fn playout(state: &mut Game, rng: &mut SplitMix64) -> GameResult {
+    while *state.result() == GameResult::InProgress {
+        let actions = state.legal_actions();
+        let action = actions[rng.below(actions.len())];
+        state.play(action).expect("legal actions can be played");
+    }
+    *state.result()
+}
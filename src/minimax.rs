@@ -1,45 +1,102 @@
-pub(crate) trait Minimax<State, Action>
-where
-    State: Clone,
-{
+use crate::alpha_beta::Window;
+
+/// The player that has to act in a state: the one trying to maximize the value, or the one trying
+/// to minimize it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// The player that wants the highest value.
+    Max,
+    /// The player that wants the lowest value.
+    Min,
+}
+
+/// A two player, zero sum game that can be searched with minimax.
+///
+/// Players don't need to alternate: the search asks [`Minimax::to_move`] on every state, so a
+/// player can act several times in a row (in Quarto a player places a piece and then chooses the
+/// next one).
+pub trait Minimax<State, Action> {
+    /// The value of a terminal `state`.
     fn utility(&self, state: &State) -> i32;
+    /// Whether the game is over in `state`.
     fn terminal(&self, state: &State) -> bool;
+    /// The actions that can be played in `state`, none when it's terminal.
     fn actions(&self, state: &State) -> Vec<Action>;
+    /// The state after playing `action` in `state`.
     fn result(&self, state: &State, action: Action) -> State;
+    /// The player that has to act in `state`.
+    fn to_move(&self, state: &State) -> Side;
+
+    /// The best value the player to move in `state` could possibly get, if there is one. Searches
+    /// stop trying actions as soon as one reaches it. By default there's none.
+    fn best_possible(&self, _state: &State) -> Option<i32> {
+        None
+    }
+
+    /// Returns the value of `state` if it's already known, otherwise calls `search` to get it.
+    /// This is where implementations can memoize values, by default nothing is remembered.
+    fn memoized<F>(&mut self, _state: &State, search: F) -> i32
+    where
+        F: FnOnce(&mut Self) -> i32,
+    {
+        search(self)
+    }
+
+    /// Like [`Minimax::memoized`], for searches that only need the value when it falls inside
+    /// `window`, see [`alpha_beta`](crate::alpha_beta::alpha_beta). Only values known to be exact
+    /// should be remembered.
+    fn memoized_within<F>(&mut self, _state: &State, _window: Window, search: F) -> i32
+    where
+        F: FnOnce(&mut Self) -> i32,
+    {
+        search(self)
+    }
 
-    // Minimax will handle 3 possible values as result of the game:
-    // 1 Player 1 wins
-    // -1 Player 2 wins
-    // 0 Draw
-    // This information will be used to do optimizations
-    fn min_value(&self, state: &State) -> i32 {
+    /// The exact value of `state`, searched with plain minimax.
+    fn value(&mut self, state: &State) -> i32 {
+        match self.to_move(state) {
+            Side::Max => self.max_value(state),
+            Side::Min => self.min_value(state),
+        }
+    }
+
+    /// The value of `state` when Min has to act.
+    fn min_value(&mut self, state: &State) -> i32 {
         if self.terminal(state) {
             return self.utility(state);
         }
 
-        let mut v = i32::MAX;
-        for action in self.actions(state) {
-            v = v.min(self.max_value(&self.result(state, action)));
-            if v == -1 {
-                return v;
+        self.memoized(state, |this| {
+            let best_possible = this.best_possible(state);
+            let mut v = i32::MAX;
+            for action in this.actions(state) {
+                let next_state = this.result(state, action);
+                v = v.min(this.value(&next_state));
+                if Some(v) == best_possible {
+                    break;
+                }
             }
-        }
-
-        v
+            v
+        })
     }
-    fn max_value(&self, state: &State) -> i32 {
+
+    /// The value of `state` when Max has to act.
+    fn max_value(&mut self, state: &State) -> i32 {
         if self.terminal(state) {
             return self.utility(state);
         }
 
-        let mut v = i32::MIN;
-        for action in self.actions(state) {
-            v = v.max(self.min_value(&self.result(state, action)));
-            if v == 1 {
-                return v;
+        self.memoized(state, |this| {
+            let best_possible = this.best_possible(state);
+            let mut v = i32::MIN;
+            for action in this.actions(state) {
+                let next_state = this.result(state, action);
+                v = v.max(this.value(&next_state));
+                if Some(v) == best_possible {
+                    break;
+                }
             }
-        }
-
-        v
+            v
+        })
     }
 }
@@ -0,0 +1,266 @@
+//! One line text notation for positions, e.g. `0.../.3../..f./.... a 1 *`
+//!
+//! The fields are separated by spaces:
+//! - The rows of the board separated by `/`, with a hex digit per piece and `.` for empty cells.
+//!   Each digit packs the attributes of the piece, the first attribute being the most significant.
+//! - The piece waiting to be placed, or `-` if a piece has to be chosen.
+//! - The player that has to move: `1` or `2`.
+//! - The result: `*` while in progress, `1` or `2` for the winner and `=` for a draw.
+
+use crate::board::Board;
+use crate::coordinate::Coordinate;
+use crate::game::{Game, GameResult, GameState, Player, Stage};
+use crate::piece::{piece_from_index, piece_to_index, Piece};
+use crate::BOARD_SIZE;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Why a position couldn't be read from its notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotationError {
+    /// The notation doesn't have exactly 4 fields.
+    WrongFieldCount(usize),
+    /// The board doesn't have 4 rows of 4 cells.
+    InvalidBoard(String),
+    /// A character that isn't a piece or `.`.
+    InvalidPiece(char),
+    /// The piece waiting to be placed isn't a single piece or `-`.
+    InvalidStage(String),
+    /// The same piece appears more than once.
+    DuplicatePiece(Piece),
+    /// The player isn't `1` or `2`.
+    InvalidPlayer(String),
+    /// The result isn't `*`, `1`, `2` or `=`.
+    InvalidResult(String),
+    /// The fields contradict each other, e.g. a game is won without a complete line.
+    Inconsistent(&'static str),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::WrongFieldCount(count) => {
+                write!(f, "Expected 4 fields separated by spaces, found {count}")
+            }
+            NotationError::InvalidBoard(board) => write!(
+                f,
+                "Invalid board {board:?}: expected {BOARD_SIZE} rows of {BOARD_SIZE} cells"
+            ),
+            NotationError::InvalidPiece(character) => write!(f, "Invalid piece {character:?}"),
+            NotationError::InvalidStage(stage) => write!(f, "Invalid stage {stage:?}"),
+            NotationError::DuplicatePiece(piece) => write!(f, "Piece {piece:?} is repeated"),
+            NotationError::InvalidPlayer(player) => write!(f, "Invalid player {player:?}"),
+            NotationError::InvalidResult(result) => write!(f, "Invalid result {result:?}"),
+            NotationError::Inconsistent(reason) => write!(f, "Inconsistent position: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+pub(crate) fn format_piece(piece: Piece) -> char {
+    char::from_digit(piece_to_index(piece) as u32, 16).expect("pieces fit in a hex digit")
+}
+
+pub(crate) fn parse_piece(character: char) -> Result<Piece, NotationError> {
+    match character.to_digit(16) {
+        Some(index) => Ok(piece_from_index(index as u8)),
+        None => Err(NotationError::InvalidPiece(character)),
+    }
+}
+
+fn format_player(player: Player) -> char {
+    match player {
+        Player::Player1 => '1',
+        Player::Player2 => '2',
+    }
+}
+
+fn parse_player(s: &str) -> Option<Player> {
+    match s {
+        "1" => Some(Player::Player1),
+        "2" => Some(Player::Player2),
+        _ => None,
+    }
+}
+
+fn take(pieces_left: &mut HashSet<Piece>, piece: Piece) -> Result<Piece, NotationError> {
+    if pieces_left.remove(&piece) {
+        Ok(piece)
+    } else {
+        Err(NotationError::DuplicatePiece(piece))
+    }
+}
+
+impl Game {
+    /// The position in the notation read by [`str::parse`], without the history.
+    pub fn to_notation(&self) -> String {
+        let board = self
+            .board
+            .grid
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.map_or('.', format_piece))
+                    .collect::<String>()
+            })
+            .join("/");
+
+        let stage = match self.game_state.stage {
+            Stage::ChoosingPieceForOponent => '-',
+            Stage::PlacingPieceGivenOponentChoice(piece) => format_piece(piece),
+        };
+
+        let result = match self.game_state.result {
+            GameResult::InProgress => '*',
+            GameResult::PlayerWon(player) => format_player(player),
+            GameResult::Draw => '=',
+        };
+
+        format!(
+            "{board} {stage} {} {result}",
+            format_player(self.game_state.player_turn)
+        )
+    }
+
+    /// Parses a position written with [`Game::to_notation`]. The pieces left are the ones that
+    /// are neither on the board nor waiting to be placed.
+    pub fn from_notation(notation: &str) -> Result<Game, NotationError> {
+        let fields = notation.split_whitespace().collect::<Vec<_>>();
+        let [board_field, stage_field, player_field, result_field] = fields[..] else {
+            return Err(NotationError::WrongFieldCount(fields.len()));
+        };
+
+        let mut pieces_left = crate::game::all_pieces();
+
+        let rows = board_field.split('/').collect::<Vec<_>>();
+        if rows.len() != BOARD_SIZE || rows.iter().any(|row| row.chars().count() != BOARD_SIZE) {
+            return Err(NotationError::InvalidBoard(board_field.to_string()));
+        }
+        let mut board = Board::new();
+        for (row_index, row) in rows.iter().enumerate() {
+            for (column_index, character) in row.chars().enumerate() {
+                if character != '.' {
+                    board.grid[row_index][column_index] =
+                        Some(take(&mut pieces_left, parse_piece(character)?)?);
+                }
+            }
+        }
+
+        let result = match result_field {
+            "*" => GameResult::InProgress,
+            "=" => GameResult::Draw,
+            _ => GameResult::PlayerWon(
+                parse_player(result_field)
+                    .ok_or_else(|| NotationError::InvalidResult(result_field.to_string()))?,
+            ),
+        };
+
+        let stage = match stage_field {
+            "-" => Stage::ChoosingPieceForOponent,
+            _ => {
+                let mut characters = stage_field.chars();
+                match (characters.next(), characters.next()) {
+                    (Some(character), None) => {
+                        let piece = parse_piece(character)?;
+                        // See `Stage::PlacingPieceGivenOponentChoice` for finished games
+                        let placed = board.grid.iter().flatten().any(|cell| *cell == Some(piece));
+                        match (result, placed) {
+                            (GameResult::InProgress, _) => {
+                                take(&mut pieces_left, piece)?;
+                            }
+                            (_, true) => {}
+                            (_, false) => {
+                                return Err(NotationError::Inconsistent(
+                                    "the game is over but the piece in hand is not on the board",
+                                ))
+                            }
+                        }
+                        Stage::PlacingPieceGivenOponentChoice(piece)
+                    }
+                    _ => return Err(NotationError::InvalidStage(stage_field.to_string())),
+                }
+            }
+        };
+
+        let player_turn = parse_player(player_field)
+            .ok_or_else(|| NotationError::InvalidPlayer(player_field.to_string()))?;
+
+        let game = Game::from_parts(
+            board,
+            GameState {
+                player_turn,
+                stage,
+                result,
+            },
+            pieces_left,
+        );
+        check_result(&game)?;
+        Ok(game)
+    }
+}
+
+// The result has to follow from the last piece placed, which is still the one in hand once the
+// game is over. A game in progress always has something to play: a piece to choose or a cell to
+// place it, unless the board is full. Choosing a piece is what passes the turn, so Player1 moves
+// after an even number of choices, and the winner is the player who placed the last piece
+fn check_result(game: &Game) -> Result<(), NotationError> {
+    let cells = || {
+        (0..BOARD_SIZE)
+            .flat_map(|row| (0..BOARD_SIZE).map(move |column| Coordinate::new(row, column)))
+    };
+    let placed = cells()
+        .filter(|cell| game.board.grid[cell.row][cell.column].is_some())
+        .count();
+    let in_hand = match (game.game_state.stage, game.game_state.result) {
+        (Stage::PlacingPieceGivenOponentChoice(_), GameResult::InProgress) => 1,
+        _ => 0,
+    };
+    let expected_player = if (placed + in_hand) % 2 == 0 {
+        Player::Player1
+    } else {
+        Player::Player2
+    };
+    if game.game_state.player_turn != expected_player {
+        return Err(NotationError::Inconsistent(
+            "the player to move doesn't match the pieces chosen",
+        ));
+    }
+    let last_placed = match game.game_state.stage {
+        Stage::PlacingPieceGivenOponentChoice(piece) => {
+            cells().find(|cell| game.board.grid[cell.row][cell.column] == Some(piece))
+        }
+        Stage::ChoosingPieceForOponent => None,
+    };
+    let won_by_last = last_placed.is_some_and(|cell| game.check_if_won(cell));
+    let board_full = game.get_empty_places().is_empty();
+    let reason = match game.game_state.result {
+        GameResult::InProgress if cells().any(|cell| game.check_if_won(cell)) => {
+            "a line is complete but the game is in progress"
+        }
+        GameResult::InProgress if board_full => "the board is full but the game is in progress",
+        GameResult::InProgress => return Ok(()),
+        _ if last_placed.is_none() => "the game is over but no piece was placed last",
+        GameResult::PlayerWon(_) if !won_by_last => {
+            "the game is won but the last piece placed doesn't complete a line"
+        }
+        GameResult::PlayerWon(winner) if winner != game.game_state.player_turn => {
+            "the game is won by the player who didn't place the last piece"
+        }
+        GameResult::Draw if won_by_last => {
+            "the game is a draw but the last piece placed completes a line"
+        }
+        GameResult::Draw if !board_full => "the game is a draw but the board isn't full",
+        _ => return Ok(()),
+    };
+    Err(NotationError::Inconsistent(reason))
+}
+
+impl FromStr for Game {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Game::from_notation(s)
+    }
+}
@@ -0,0 +1,256 @@
+//! Solving on several threads by splitting the actions of the root among them. The threads share
+//! the values they find, so work done by one of them is not repeated by the others.
+
+use crate::alpha_beta::{alpha_beta, Window};
+use crate::bitboard::BitGame;
+use crate::error::QuartoError;
+use crate::game::{Game, QuartoAction};
+use crate::minimax::{Minimax, Side};
+use crate::quarto_minimax::{is_exact, memory_key, priority, QuartoMinimax};
+use crate::transposition::TranspositionTable;
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// Enough shards for threads to rarely wait for each other
+const N_SHARDS: usize = 64;
+
+/// Values found by the threads, split in shards by the hash of the state so threads storing
+/// different states don't block each other.
+enum SharedMemory {
+    /// Added to the solver's memory once the search ends.
+    Maps {
+        shards: Vec<Mutex<HashMap<BitGame, i32>>>,
+        hasher: RandomState,
+    },
+    /// The solver's table itself, so the memory used stays bounded during the search.
+    Table {
+        shards: Vec<Mutex<TranspositionTable>>,
+        buckets: usize,
+    },
+}
+
+impl SharedMemory {
+    fn new(table: Option<TranspositionTable>) -> SharedMemory {
+        match table {
+            Some(table) => {
+                let buckets = table.buckets();
+                SharedMemory::Table {
+                    shards: table
+                        .split(N_SHARDS.min(buckets))
+                        .into_iter()
+                        .map(Mutex::new)
+                        .collect(),
+                    buckets,
+                }
+            }
+            None => SharedMemory::Maps {
+                shards: (0..N_SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+                hasher: RandomState::new(),
+            },
+        }
+    }
+
+    fn get(&self, key: &BitGame) -> Option<i32> {
+        match self {
+            SharedMemory::Maps { shards, hasher } => shards
+                [hasher.hash_one(key) as usize % shards.len()]
+            .lock()
+            .expect("no thread panics holding the lock")
+            .get(key)
+            .copied(),
+            SharedMemory::Table { shards, buckets } => {
+                let key = key.key();
+                shards[TranspositionTable::shard(key, *buckets, shards.len())]
+                    .lock()
+                    .expect("no thread panics holding the lock")
+                    .get(key)
+            }
+        }
+    }
+
+    fn insert(&self, key: BitGame, value: i32) {
+        match self {
+            SharedMemory::Maps { shards, hasher } => {
+                shards[hasher.hash_one(key) as usize % shards.len()]
+                    .lock()
+                    .expect("no thread panics holding the lock")
+                    .insert(key, value);
+            }
+            SharedMemory::Table { shards, buckets } => {
+                let hash = key.key();
+                shards[TranspositionTable::shard(hash, *buckets, shards.len())]
+                    .lock()
+                    .expect("no thread panics holding the lock")
+                    .insert(hash, value, priority(&key));
+            }
+        }
+    }
+
+    // Hands the values over to the solver the memory was created for
+    fn finish(self, solver: &mut QuartoMinimax) {
+        match self {
+            SharedMemory::Maps { shards, .. } => {
+                for shard in shards {
+                    let shard = shard
+                        .into_inner()
+                        .expect("no thread panics holding the lock");
+                    for (key, value) in shard {
+                        solver.remember(key, value);
+                    }
+                }
+            }
+            SharedMemory::Table { shards, .. } => {
+                let shards = shards
+                    .into_iter()
+                    .map(|shard| {
+                        shard
+                            .into_inner()
+                            .expect("no thread panics holding the lock")
+                    })
+                    .collect();
+                solver.table = Some(TranspositionTable::join(shards));
+            }
+        }
+    }
+}
+
+/// The search done by each thread: the rules and the values solved before the search started come
+/// from the solver, and the values found during the search go to the shared memory.
+struct Worker<'a> {
+    solver: &'a QuartoMinimax,
+    memory: &'a SharedMemory,
+}
+
+impl Minimax<BitGame, QuartoAction> for Worker<'_> {
+    fn utility(&self, state: &BitGame) -> i32 {
+        self.solver.utility(state)
+    }
+
+    fn terminal(&self, state: &BitGame) -> bool {
+        self.solver.terminal(state)
+    }
+
+    fn actions(&self, state: &BitGame) -> Vec<QuartoAction> {
+        self.solver.actions(state)
+    }
+
+    fn result(&self, state: &BitGame, action: QuartoAction) -> BitGame {
+        Minimax::result(self.solver, state, action)
+    }
+
+    fn to_move(&self, state: &BitGame) -> Side {
+        self.solver.to_move(state)
+    }
+
+    fn best_possible(&self, state: &BitGame) -> Option<i32> {
+        self.solver.best_possible(state)
+    }
+
+    fn memoized_within<F>(&mut self, state: &BitGame, window: Window, search: F) -> i32
+    where
+        F: FnOnce(&mut Self) -> i32,
+    {
+        let key = memory_key(state, self.solver.use_symmetries);
+        if let Some(value) = self.solver.recall(&key) {
+            return value;
+        }
+        if let Some(value) = self.memory.get(&key) {
+            return value;
+        }
+
+        let value = search(self);
+        if is_exact(state, window, value) {
+            self.memory.insert(key, value);
+        }
+        value
+    }
+}
+
+impl QuartoMinimax {
+    /// Like [`QuartoMinimax::best_action`], searching the actions on `threads` threads at the same
+    /// time. The values found are added to the memory, or stored in the table as they are found.
+    pub fn best_action_parallel(
+        &mut self,
+        state: &Game,
+        threads: usize,
+    ) -> Result<(QuartoAction, i32), QuartoError> {
+        if self.terminal(state) {
+            return Err(QuartoError::GameOver(state.game_state.result));
+        }
+
+        let side = self.to_move(state);
+        let worst = match side {
+            Side::Max => i32::MIN,
+            Side::Min => i32::MAX,
+        };
+        let best_possible = self.best_possible(state);
+
+        let root = BitGame::from(state);
+        let actions = root.legal_actions();
+        // The threads share the table instead of the solver while they search
+        let memory = SharedMemory::new(self.table.take());
+        let next_action = AtomicUsize::new(0);
+        // The best value found so far, only actions that can beat it need to be searched exactly
+        let best_value = AtomicI32::new(worst);
+        let done = AtomicBool::new(false);
+        let results = Mutex::new(Vec::with_capacity(actions.len()));
+
+        thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                scope.spawn(|| {
+                    let mut worker = Worker {
+                        solver: self,
+                        memory: &memory,
+                    };
+                    while !done.load(Ordering::Relaxed) {
+                        let Some(action) = actions.get(next_action.fetch_add(1, Ordering::Relaxed))
+                        else {
+                            break;
+                        };
+
+                        let best = best_value.load(Ordering::Relaxed);
+                        let window = match (side, best) {
+                            (_, best) if best == worst => Window::FULL,
+                            (Side::Max, best) => Window::new(best, i32::MAX),
+                            (Side::Min, best) => Window::new(i32::MIN, best),
+                        };
+                        let next_state = worker.result(&root, *action);
+                        let value = alpha_beta(&mut worker, &next_state, window);
+
+                        match side {
+                            Side::Max => best_value.fetch_max(value, Ordering::Relaxed),
+                            Side::Min => best_value.fetch_min(value, Ordering::Relaxed),
+                        };
+                        if Some(value) == best_possible {
+                            done.store(true, Ordering::Relaxed);
+                        }
+                        // Values outside the window are just bounds, but those actions weren't
+                        // better than another one anyway
+                        if window.contains(value) {
+                            results
+                                .lock()
+                                .expect("no thread panics holding the lock")
+                                .push((*action, value));
+                        }
+                    }
+                });
+            }
+        });
+
+        memory.finish(self);
+
+        let results = results
+            .into_inner()
+            .expect("no thread panics holding the lock");
+        let best = match side {
+            Side::Max => results.into_iter().max_by_key(|(_, value)| *value),
+            Side::Min => results.into_iter().min_by_key(|(_, value)| *value),
+        };
+        Ok(best.expect("a game in progress always has actions"))
+    }
+}
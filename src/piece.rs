@@ -2,7 +2,9 @@ use super::QUATRO;
 
 use super::N_PROPERTIES;
 
-pub(crate) type Piece = [bool; N_PROPERTIES];
+/// A piece is described by its attributes: two pieces share an attribute when they have the same
+/// value at the same index.
+pub type Piece = [bool; N_PROPERTIES];
 
 pub(crate) fn check_match(pieces: Vec<Piece>) -> bool {
     for property in 0..QUATRO {
@@ -22,3 +24,19 @@ pub(crate) fn check_match(pieces: Vec<Piece>) -> bool {
 
     false
 }
+
+/// Packs the attributes of `piece` into the lowest bits of a number, the first attribute being the
+/// most significant one.
+pub(crate) fn piece_to_index(piece: Piece) -> u8 {
+    piece
+        .iter()
+        .fold(0, |index, &attribute| (index << 1) | attribute as u8)
+}
+
+pub(crate) fn piece_from_index(index: u8) -> Piece {
+    let mut piece = [false; N_PROPERTIES];
+    for (position, attribute) in piece.iter_mut().enumerate() {
+        *attribute = index & (1 << (N_PROPERTIES - position - 1)) != 0;
+    }
+    piece
+}
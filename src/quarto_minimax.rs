@@ -1,142 +1,428 @@
-use crate::coordinate::Coordinate;
+use crate::alpha_beta::Window;
+use crate::bitboard::BitGame;
+use crate::book::Book;
+use crate::compact::SymmetryMode;
+use crate::error::QuartoError;
 use crate::game;
 use crate::game::Game;
 use crate::game::GameResult;
-use crate::piece;
+use crate::game::QuartoAction;
+use crate::game::Stage;
+use crate::journal::Journal;
+use crate::minimax::{Minimax, Side};
+use crate::transposition::TranspositionTable;
+use crate::{BOARD_SIZE, N_PROPERTIES};
 
 use std::collections::HashMap;
 
-pub(crate) struct QuartoMinimax {
-    pub(crate) state_to_value: HashMap<Game, i32>,
-}
-
-#[derive(Copy, Clone, Debug)]
-pub(crate) enum QuartoAction {
-    Choose(piece::Piece),
-    Put(Coordinate),
+/// Exhaustive solver for Quarto positions. Values are given from Player1's perspective: positive
+/// if Player1 wins, negative if Player2 wins and 0 for a draw. The sooner the game is won the
+/// larger the value is in absolute terms, so the winner goes for the fastest win and the loser
+/// for the slowest loss, see [`QuartoMinimax::plies_to_win`].
+///
+/// Positions are given as [`Game`]s but searched and stored as [`BitGame`]s, which are much
+/// cheaper to copy, hash and compare.
+pub struct QuartoMinimax {
+    pub(crate) state_to_value: HashMap<BitGame, i32>,
+    pub(crate) use_symmetries: bool,
+    // When set, values are stored here instead of in `state_to_value`
+    pub(crate) table: Option<TranspositionTable>,
+    // When set, values stored in `state_to_value` are also logged here
+    pub(crate) journal: Option<Journal>,
+    // When set, values not stored are looked up here
+    pub(crate) book: Option<Book>,
 }
 
 impl QuartoMinimax {
-    pub(crate) fn new(memory: HashMap<Game, i32>) -> QuartoMinimax {
+    /// Creates a solver reusing the values already computed in `memory`. States are stored in
+    /// their canonical form, see [`canonical`].
+    ///
+    /// [`canonical`]: crate::canonical
+    pub fn new(memory: HashMap<Game, i32>) -> QuartoMinimax {
         QuartoMinimax {
-            state_to_value: memory,
+            state_to_value: memory
+                .iter()
+                .map(|(game, value)| (BitGame::from(game), *value))
+                .collect(),
+            use_symmetries: true,
+            table: None,
+            journal: None,
+            book: None,
         }
     }
-}
 
-// TODO: implementation for minimax is totally custom due to the actions not being the entire turn (2 actions per turn). When trying to implement the trait, I couln't figure out how to handle the imparity of the actions: a turn consists of "putting" a piece and then "choosing" one, but it's not aligned with how the game starts and ends by first "choosing" a piece and then "putting" it. This impacts in the implementation of the "actions" and "result" methods
-impl QuartoMinimax {
-    // We'll take into account the perspective of player 1 to calculate the utility
-    // This function only makes sense for terminal states
-    pub(crate) fn utility(&self, state: &Game) -> i32 {
-        match state.game_state.result {
-            GameResult::Draw => 0,
-            GameResult::PlayerWon(player) => match player {
-                game::Player::Player1 => 1,
-                game::Player::Player2 => -1,
-            },
-            GameResult::InProgress => {
-                panic!("Utility function called on non terminal state")
-            }
+    /// Creates a solver storing values in `table`, so it never uses more memory than that. Values
+    /// stored this way can't be saved, [`QuartoMinimax::memory`] stays empty.
+    pub fn with_table(table: TranspositionTable) -> QuartoMinimax {
+        QuartoMinimax {
+            table: Some(table),
+            ..QuartoMinimax::new(HashMap::new())
         }
     }
 
-    pub(crate) fn terminal(&self, state: &Game) -> bool {
-        match state.game_state.result {
-            GameResult::Draw => true,
-            GameResult::PlayerWon(_) => true,
-            GameResult::InProgress => false,
-        }
+    /// Whether states are stored in their canonical form, so the values of equivalent states are
+    /// shared. Memories built with and without symmetries shouldn't be mixed: values are still
+    /// correct, but lookups in the other kind of memory will mostly miss.
+    pub fn set_use_symmetries(&mut self, use_symmetries: bool) {
+        self.use_symmetries = use_symmetries;
     }
 
-    pub(crate) fn actions(&self, state: &Game) -> Vec<QuartoAction> {
-        match state.game_state.stage {
-            game::Stage::ChoosingPieceForOponent => state
-                .get_pieces_left()
-                .iter()
-                .map(|piece| QuartoAction::Choose(*piece))
-                .collect(),
-            game::Stage::PlacingPieceGivenOponentChoice(_) => state
-                .get_empty_places()
-                .iter()
-                .map(|position| QuartoAction::Put(*position))
-                .collect(),
+    /// How the states of [`QuartoMinimax::memory`] are stored, to be recorded when it's saved.
+    pub fn symmetry_mode(&self) -> SymmetryMode {
+        match self.use_symmetries {
+            true => SymmetryMode::Canonical,
+            false => SymmetryMode::Positions,
         }
     }
 
-    pub(crate) fn result(&self, state: &Game, action: QuartoAction) -> Game {
-        let mut new_state = state.clone();
-        match action {
-            QuartoAction::Choose(piece) => {
-                new_state.choose(piece).unwrap();
-                new_state
+    /// Values of every state solved so far, turned into games.
+    pub fn memory(&self) -> HashMap<Game, i32> {
+        self.state_to_value
+            .iter()
+            .map(|(state, value)| (state.to_game(), *value))
+            .collect()
+    }
+
+    /// Number of states in [`QuartoMinimax::memory`].
+    pub fn memory_len(&self) -> usize {
+        self.state_to_value.len()
+    }
+
+    /// Logs every value found from now on to `journal`, see [`Journal`]. Solvers with a table
+    /// don't log anything.
+    pub fn set_journal(&mut self, journal: Journal) {
+        self.journal = Some(journal);
+    }
+
+    /// The journal set with [`QuartoMinimax::set_journal`], e.g. to sync it.
+    pub fn journal_mut(&mut self) -> Option<&mut Journal> {
+        self.journal.as_mut()
+    }
+
+    /// Looks up the values of the states not solved yet in `book` before searching them.
+    pub fn set_book(&mut self, book: Book) {
+        self.book = Some(book);
+    }
+
+    /// The table values are stored in, if the solver was created with one.
+    pub fn table(&self) -> Option<&TranspositionTable> {
+        self.table.as_ref()
+    }
+
+    pub(crate) fn recall(&self, key: &BitGame) -> Option<i32> {
+        let stored = match &self.table {
+            Some(table) => table.get(key.key()),
+            None => self.state_to_value.get(key).copied(),
+        };
+        stored.or_else(|| {
+            let book = self.book.as_ref()?;
+            match self.use_symmetries {
+                true => book.get_canonical(key.key()),
+                false => book.get_canonical(key.canonical().key()),
             }
-            QuartoAction::Put(position) => {
-                new_state.put(position).unwrap();
-                new_state
+        })
+    }
+
+    pub(crate) fn remember(&mut self, key: BitGame, value: i32) {
+        match &mut self.table {
+            Some(table) => table.insert(key.key(), value, priority(&key)),
+            None => {
+                if let Some(journal) = &mut self.journal {
+                    journal.append_state(&key, value);
+                }
+                self.state_to_value.insert(key, value);
             }
         }
     }
+}
 
-    pub(crate) fn min_value(&mut self, state: &Game) -> i32 {
-        if state.game_state.player_turn != game::Player::Player2 {
-            panic!("Min value called on a state where it's not player 2 turn");
-        }
+// Priority of the value of a state in a table. States with more pieces left save larger searches,
+// so they are kept longer
+pub(crate) fn priority(key: &BitGame) -> u8 {
+    key.pieces_left_count() as u8
+}
 
-        match self.state_to_value.get(state) {
-            Some(value) => return *value,
-            None => (),
-        }
+// Key of the state in the memory
+pub(crate) fn memory_key(state: &BitGame, use_symmetries: bool) -> BitGame {
+    match use_symmetries {
+        true => state.canonical(),
+        false => *state,
+    }
+}
 
-        if self.terminal(state) {
-            return self.utility(state);
+// What the values of a state depend on, for both representations
+pub(crate) trait Position {
+    fn pieces_left_count(&self) -> usize;
+    fn result(&self) -> GameResult;
+    fn placing(&self) -> bool;
+    fn player_turn(&self) -> game::Player;
+}
+
+impl Position for Game {
+    fn pieces_left_count(&self) -> usize {
+        self.pieces_left.len()
+    }
+
+    fn result(&self) -> GameResult {
+        self.game_state.result
+    }
+
+    fn placing(&self) -> bool {
+        matches!(
+            self.game_state.stage,
+            Stage::PlacingPieceGivenOponentChoice(_)
+        )
+    }
+
+    fn player_turn(&self) -> game::Player {
+        self.game_state.player_turn
+    }
+}
+
+impl Position for BitGame {
+    fn pieces_left_count(&self) -> usize {
+        BitGame::pieces_left_count(self)
+    }
+
+    fn result(&self) -> GameResult {
+        BitGame::result(self)
+    }
+
+    fn placing(&self) -> bool {
+        matches!(self.stage(), Stage::PlacingPieceGivenOponentChoice(_))
+    }
+
+    fn player_turn(&self) -> game::Player {
+        BitGame::player_turn(self)
+    }
+}
+
+// Actions in a whole game: every piece is chosen and then placed
+const MAX_PLIES: i32 = 2 * (BOARD_SIZE * BOARD_SIZE) as i32;
+
+// Number of actions played to reach `state`. Pieces left are the ones not chosen yet, except at the
+// end of the game, see `Stage::PlacingPieceGivenOponentChoice`
+fn plies<P: Position>(state: &P) -> i32 {
+    let chosen = (1 << N_PROPERTIES) - state.pieces_left_count() as i32;
+    match (state.result(), state.placing()) {
+        (GameResult::InProgress, true) => 2 * chosen - 1,
+        _ => 2 * chosen,
+    }
+}
+
+// Value of a game won by `player` after `plies` actions
+fn win_value(player: game::Player, plies: i32) -> i32 {
+    let value = MAX_PLIES + 1 - plies;
+    match player {
+        game::Player::Player1 => value,
+        game::Player::Player2 => -value,
+    }
+}
+
+// Value of `player` winning as soon as possible from `state`, in progress. Players can only win
+// when placing a piece, and each one chooses the piece the other places
+fn soonest_win<P: Position>(state: &P, player: game::Player) -> i32 {
+    let to_move = state.player_turn() == player;
+    let plies_needed = match (to_move, state.placing()) {
+        (true, true) => 1,
+        (false, false) => 2,
+        (false, true) => 3,
+        (true, false) => 4,
+    };
+    win_value(player, plies(state) + plies_needed)
+}
+
+// Whether a value of `state` returned by a search with `window` can be remembered. Besides values
+// inside the window, a bound is exact when it's already the soonest possible win of a player
+pub(crate) fn is_exact<P: Position>(state: &P, window: Window, value: i32) -> bool {
+    window.contains(value)
+        || (value >= window.beta && value == soonest_win(state, game::Player::Player1))
+        || (value <= window.alpha && value == soonest_win(state, game::Player::Player2))
+}
+
+// We'll take into account the perspective of player 1 to calculate the utility
+// This function only makes sense for terminal states
+fn utility<P: Position>(state: &P) -> i32 {
+    match state.result() {
+        GameResult::Draw => 0,
+        GameResult::PlayerWon(player) => win_value(player, plies(state)),
+        GameResult::InProgress => {
+            panic!("Utility function called on non terminal state")
         }
+    }
+}
 
-        let m_value = match state.game_state.stage {
-            game::Stage::PlacingPieceGivenOponentChoice(_) => QuartoMinimax::min_value,
-            game::Stage::ChoosingPieceForOponent => QuartoMinimax::max_value,
-        };
+// The player placing a piece is the same one choosing the next piece
+fn to_move<P: Position>(state: &P) -> Side {
+    match state.player_turn() {
+        game::Player::Player1 => Side::Max,
+        game::Player::Player2 => Side::Min,
+    }
+}
 
-        let mut v = i32::MAX;
-        for action in self.actions(state) {
-            v = v.min(m_value(self, &self.result(state, action)));
-            if v == -1 {
-                break;
-            }
+/// The rules on [`Game`]s. Values are searched on the [`BitGame`] of the state.
+impl Minimax<Game, QuartoAction> for QuartoMinimax {
+    fn utility(&self, state: &Game) -> i32 {
+        utility(state)
+    }
+
+    fn terminal(&self, state: &Game) -> bool {
+        state.game_state.result != GameResult::InProgress
+    }
+
+    fn actions(&self, state: &Game) -> Vec<QuartoAction> {
+        state.legal_actions()
+    }
+
+    fn result(&self, state: &Game, action: QuartoAction) -> Game {
+        QuartoMinimax::result(self, state, action).expect("actions only returns legal actions")
+    }
+
+    fn to_move(&self, state: &Game) -> Side {
+        to_move(state)
+    }
+
+    fn best_possible(&self, state: &Game) -> Option<i32> {
+        Some(soonest_win(state, state.game_state.player_turn))
+    }
+
+    fn memoized<F>(&mut self, state: &Game, search: F) -> i32
+    where
+        F: FnOnce(&mut Self) -> i32,
+    {
+        self.memoized(&BitGame::from(state), search)
+    }
+
+    fn memoized_within<F>(&mut self, state: &Game, window: Window, search: F) -> i32
+    where
+        F: FnOnce(&mut Self) -> i32,
+    {
+        self.memoized_within(&BitGame::from(state), window, search)
+    }
+
+    fn value(&mut self, state: &Game) -> i32 {
+        self.value(&BitGame::from(state))
+    }
+}
+
+impl Minimax<BitGame, QuartoAction> for QuartoMinimax {
+    fn utility(&self, state: &BitGame) -> i32 {
+        utility(state)
+    }
+
+    fn terminal(&self, state: &BitGame) -> bool {
+        state.result() != GameResult::InProgress
+    }
+
+    fn actions(&self, state: &BitGame) -> Vec<QuartoAction> {
+        state.legal_actions()
+    }
+
+    fn result(&self, state: &BitGame, action: QuartoAction) -> BitGame {
+        let mut new_state = *state;
+        new_state
+            .play(action)
+            .expect("actions only returns legal actions");
+        new_state
+    }
+
+    fn to_move(&self, state: &BitGame) -> Side {
+        to_move(state)
+    }
+
+    fn best_possible(&self, state: &BitGame) -> Option<i32> {
+        Some(soonest_win(state, state.player_turn()))
+    }
+
+    fn memoized<F>(&mut self, state: &BitGame, search: F) -> i32
+    where
+        F: FnOnce(&mut Self) -> i32,
+    {
+        let key = memory_key(state, self.use_symmetries);
+        if let Some(value) = self.recall(&key) {
+            return value;
         }
 
-        self.state_to_value.insert(state.clone(), v);
-        v
+        let value = search(self);
+        self.remember(key, value);
+        value
     }
-    pub(crate) fn max_value(&mut self, state: &Game) -> i32 {
-        if state.game_state.player_turn != game::Player::Player1 {
-            panic!("Max value called on a state where it's not player 1 turn");
+
+    fn memoized_within<F>(&mut self, state: &BitGame, window: Window, search: F) -> i32
+    where
+        F: FnOnce(&mut Self) -> i32,
+    {
+        let key = memory_key(state, self.use_symmetries);
+        if let Some(value) = self.recall(&key) {
+            return value;
         }
 
-        match self.state_to_value.get(state) {
-            Some(value) => return *value,
-            None => (),
+        let value = search(self);
+        if is_exact(state, window, value) {
+            self.remember(key, value);
         }
+        value
+    }
+}
+
+impl QuartoMinimax {
+    /// Number of actions until the game is won, given the `value` of `state`, when the winner
+    /// plays the fastest win and the loser the slowest loss. `None` if it's a draw.
+    pub fn plies_to_win(state: &Game, value: i32) -> Option<u32> {
+        (value != 0).then(|| (MAX_PLIES + 1 - value.abs() - plies(state)) as u32)
+    }
 
+    /// The value of `state` if it's already solved, in the memory or the book, without searching.
+    pub fn known_value(&self, state: &Game) -> Option<i32> {
+        match self.terminal(state) {
+            true => Some(self.utility(state)),
+            false => self.recall(&memory_key(&BitGame::from(state), self.use_symmetries)),
+        }
+    }
+
+    /// Like [`Minimax::result`], but returning an error for illegal actions instead of panicking.
+    /// Only the position is copied, the new state's history is just `action`.
+    pub fn result(&self, state: &Game, action: QuartoAction) -> Result<Game, QuartoError> {
+        let mut new_state = state.position();
+        new_state.play(action)?;
+        Ok(new_state)
+    }
+
+    /// Every legal action paired with the value of the state it leads to.
+    pub fn evaluate_all_actions(&mut self, state: &Game) -> Vec<(QuartoAction, i32)> {
+        self.actions(state)
+            .into_iter()
+            .map(|action| {
+                let next_state = Minimax::result(self, state, action);
+                (action, self.value(&next_state))
+            })
+            .collect()
+    }
+
+    /// The action with the best value for the player that has to move, together with that value.
+    pub fn best_action(&mut self, state: &Game) -> Result<(QuartoAction, i32), QuartoError> {
         if self.terminal(state) {
-            return self.utility(state);
+            return Err(QuartoError::GameOver(state.game_state.result));
         }
 
-        let m_value = match state.game_state.stage {
-            game::Stage::PlacingPieceGivenOponentChoice(_) => QuartoMinimax::max_value,
-            game::Stage::ChoosingPieceForOponent => QuartoMinimax::min_value,
+        let better: fn(i32, i32) -> bool = match self.to_move(state) {
+            Side::Max => |value, best| value > best,
+            Side::Min => |value, best| value < best,
         };
+        let best_possible = self.best_possible(state);
 
-        let mut v = i32::MIN;
+        let mut best: Option<(QuartoAction, i32)> = None;
         for action in self.actions(state) {
-            v = v.max(m_value(self, &self.result(state, action)));
-            if v == 1 {
+            let next_state = Minimax::result(self, state, action);
+            let value = self.value(&next_state);
+            if best.is_none_or(|(_, best_value)| better(value, best_value)) {
+                best = Some((action, value));
+            }
+            if Some(value) == best_possible {
                 break;
             }
         }
 
-        self.state_to_value.insert(state.clone(), v);
-
-        v
+        Ok(best.expect("a game in progress always has actions"))
     }
 }
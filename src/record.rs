@@ -0,0 +1,248 @@
+//! Game records, loosely based on chess' PGN:
+//!
+//! ```text
+//! [Player1 "Alice"]
+//! [Player2 "Bob"]
+//! [Date "2022-09-20"]
+//!
+//! 1. 0 a1 2. 5 b2 3. f c1 ... 1-0
+//! ```
+//!
+//! Metadata tags come first, one per line. Then the actions follow: a piece being chosen is
+//! written as in the position notation (a hex digit), and a placement as the column letter
+//! followed by the row number. Move numbers are optional and the record ends with the result:
+//! `1-0` if Player1 won, `0-1` if Player2 won, `1/2-1/2` for a draw and `*` for a game in progress.
+//! A record whose result isn't the one its actions lead to is rejected.
+
+use crate::coordinate::Coordinate;
+use crate::error::QuartoError;
+use crate::game::{Game, GameResult, Player, QuartoAction};
+use crate::notation::{format_piece, parse_piece};
+use crate::BOARD_SIZE;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Why a game record couldn't be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecordError {
+    /// A metadata line that isn't of the form `[Key "Value"]`.
+    InvalidTag(String),
+    /// A token that isn't an action.
+    InvalidAction(String),
+    /// The action at the given index can't be played.
+    IllegalAction(usize, QuartoError),
+    /// The result written at the end isn't the one of the game.
+    WrongResult {
+        /// The result written.
+        written: GameResult,
+        /// The result after playing the actions.
+        actual: GameResult,
+    },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::InvalidTag(line) => write!(f, "Invalid metadata tag {line:?}"),
+            RecordError::InvalidAction(token) => write!(f, "Invalid action {token:?}"),
+            RecordError::IllegalAction(index, error) => {
+                write!(f, "Action number {} can't be played: {error}", index + 1)
+            }
+            RecordError::WrongResult { written, actual } => write!(
+                f,
+                "The record ends with {} but the game's result is {}",
+                format_result(written),
+                format_result(actual)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// The actions of a game, in the order they were played, together with some metadata.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GameRecord {
+    /// Tags such as player names or the date, in the order they are written.
+    pub metadata: Vec<(String, String)>,
+    /// The actions played, starting from the empty board.
+    pub actions: Vec<QuartoAction>,
+}
+
+impl GameRecord {
+    /// A record of `actions`, without metadata.
+    pub fn new(actions: Vec<QuartoAction>) -> GameRecord {
+        GameRecord {
+            metadata: vec![],
+            actions,
+        }
+    }
+
+    /// The value of the metadata tag `key`, if there's one.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(tag, _)| tag == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a tag, replacing the previous one if any.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.metadata.iter_mut().find(|(tag, _)| tag == key) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.metadata.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// Plays the actions from the start, returning every position: the initial one, and the one
+    /// after each action.
+    pub fn replay(&self) -> Result<Vec<Game>, RecordError> {
+        let mut game = Game::new();
+        let mut positions = vec![game.clone()];
+
+        for (index, action) in self.actions.iter().enumerate() {
+            game.play(*action)
+                .map_err(|error| RecordError::IllegalAction(index, error))?;
+            positions.push(game.clone());
+        }
+
+        Ok(positions)
+    }
+
+    /// The position after playing every action.
+    pub fn final_position(&self) -> Result<Game, RecordError> {
+        Ok(self
+            .replay()?
+            .pop()
+            .expect("replay always includes the initial position"))
+    }
+}
+
+// Also used for the moves typed on the terminal
+pub(crate) fn format_action(action: QuartoAction) -> String {
+    match action {
+        QuartoAction::Choose(piece) => format_piece(piece).to_string(),
+        QuartoAction::Put(Coordinate { row, column }) => {
+            format!("{}{}", (b'a' + column as u8) as char, row + 1)
+        }
+    }
+}
+
+pub(crate) fn parse_action(token: &str) -> Result<QuartoAction, RecordError> {
+    let invalid = || RecordError::InvalidAction(token.to_string());
+    let characters = token.chars().collect::<Vec<_>>();
+
+    match characters[..] {
+        [piece] => Ok(QuartoAction::Choose(
+            parse_piece(piece).map_err(|_| invalid())?,
+        )),
+        [column, row] => {
+            let column = (column as usize).wrapping_sub('a' as usize);
+            let row = row
+                .to_digit(10)
+                .map(|row| (row as usize).wrapping_sub(1))
+                .ok_or_else(invalid)?;
+            if row >= BOARD_SIZE || column >= BOARD_SIZE {
+                return Err(invalid());
+            }
+            Ok(QuartoAction::Put(Coordinate { row, column }))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn format_result(result: &GameResult) -> &'static str {
+    match result {
+        GameResult::InProgress => "*",
+        GameResult::PlayerWon(Player::Player1) => "1-0",
+        GameResult::PlayerWon(Player::Player2) => "0-1",
+        GameResult::Draw => "1/2-1/2",
+    }
+}
+
+fn parse_result(token: &str) -> Option<GameResult> {
+    match token {
+        "*" => Some(GameResult::InProgress),
+        "1-0" => Some(GameResult::PlayerWon(Player::Player1)),
+        "0-1" => Some(GameResult::PlayerWon(Player::Player2)),
+        "1/2-1/2" => Some(GameResult::Draw),
+        _ => None,
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (key, value) in &self.metadata {
+            writeln!(f, "[{key} \"{}\"]", value.replace('"', "\\\""))?;
+        }
+        if !self.metadata.is_empty() {
+            writeln!(f)?;
+        }
+
+        // Every move number is followed by the piece chosen and where the opponent placed it
+        let mut tokens = vec![];
+        for (index, action) in self.actions.iter().enumerate() {
+            if index % 2 == 0 {
+                tokens.push(format!("{}.", index / 2 + 1));
+            }
+            tokens.push(format_action(*action));
+        }
+
+        let result = match self.final_position() {
+            Ok(game) => format_result(game.result()),
+            Err(_) => "*",
+        };
+        tokens.push(result.to_string());
+
+        writeln!(f, "{}", tokens.join(" "))
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    // Checking the result needs the actions to be played, so records with an illegal action and a
+    // result are rejected too
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::default();
+        let mut written_result = None;
+
+        for line in s.lines().map(str::trim) {
+            if line.starts_with('[') {
+                let tag = line
+                    .strip_prefix('[')
+                    .and_then(|tag| tag.strip_suffix(']'))
+                    .and_then(|tag| tag.split_once(' '))
+                    .and_then(|(key, value)| {
+                        let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+                        Some((key.to_string(), value.replace("\\\"", "\"")))
+                    })
+                    .ok_or_else(|| RecordError::InvalidTag(line.to_string()))?;
+                record.metadata.push(tag);
+                continue;
+            }
+
+            for token in line.split_whitespace() {
+                let is_move_number = token
+                    .strip_suffix('.')
+                    .is_some_and(|number| number.parse::<usize>().is_ok());
+                if is_move_number {
+                    continue;
+                }
+                match parse_result(token) {
+                    Some(result) => written_result = Some(result),
+                    None => record.actions.push(parse_action(token)?),
+                }
+            }
+        }
+
+        if let Some(written) = written_result {
+            let actual = *record.final_position()?.result();
+            if written != actual {
+                return Err(RecordError::WrongResult { written, actual });
+            }
+        }
+        Ok(record)
+    }
+}
@@ -0,0 +1,250 @@
+//! Canonical forms of positions under the symmetries of Quarto.
+//!
+//! Two positions are equivalent when one can be turned into the other by:
+//! - Moving the cells around in a way that maps lines to lines. Besides rotations and reflections
+//!   this includes swapping the inner and outer rows and columns, 32 symmetries in total.
+//! - Permuting the attributes of every piece.
+//! - Inverting some attributes of every piece.
+//!
+//! Equivalent positions have the same value, so the solver only needs to store one of them.
+
+use crate::board::Board;
+use crate::game::{Game, GameState, Stage};
+use crate::piece::{piece_from_index, piece_to_index};
+use crate::{BOARD_SIZE, N_PROPERTIES};
+
+use std::cmp::Ordering;
+
+const N_CELLS: usize = BOARD_SIZE * BOARD_SIZE;
+const N_PIECES: usize = 1 << N_PROPERTIES;
+const EMPTY: u8 = N_PIECES as u8;
+
+const N_PERMUTATIONS: usize = 24;
+
+const fn permutations() -> [[usize; 4]; N_PERMUTATIONS] {
+    let mut permutations = [[0; 4]; N_PERMUTATIONS];
+    let mut count = 0;
+    let mut a = 0;
+    while a < 4 {
+        let mut b = 0;
+        while b < 4 {
+            let mut c = 0;
+            while c < 4 {
+                // The fourth element is the one missing
+                if a != b && a != c && b != c {
+                    permutations[count] = [a, b, c, 6 - a - b - c];
+                    count += 1;
+                }
+                c += 1;
+            }
+            b += 1;
+        }
+        a += 1;
+    }
+    permutations
+}
+
+const PERMUTATIONS: [[usize; 4]; N_PERMUTATIONS] = permutations();
+
+// Permuting the rows and columns with the same permutation keeps both diagonals as long as the
+// permutation commutes with reversing the line
+const fn line_permutations() -> [[usize; BOARD_SIZE]; 8] {
+    let mut line_permutations = [[0; BOARD_SIZE]; 8];
+    let mut count = 0;
+    let mut index = 0;
+    while index < N_PERMUTATIONS {
+        let permutation = PERMUTATIONS[index];
+        let mut commutes = true;
+        let mut i = 0;
+        while i < BOARD_SIZE {
+            if permutation[BOARD_SIZE - 1 - i] != BOARD_SIZE - 1 - permutation[i] {
+                commutes = false;
+            }
+            i += 1;
+        }
+        if commutes {
+            line_permutations[count] = permutation;
+            count += 1;
+        }
+        index += 1;
+    }
+    line_permutations
+}
+
+const N_BOARD_SYMMETRIES: usize = 32;
+
+// For every symmetry, the cell where the content of each cell ends up
+const fn board_symmetries() -> [[usize; N_CELLS]; N_BOARD_SYMMETRIES] {
+    let line_permutations = line_permutations();
+    let mut symmetries = [[0; N_CELLS]; N_BOARD_SYMMETRIES];
+    let mut count = 0;
+    let mut index = 0;
+    while index < line_permutations.len() {
+        let rows = line_permutations[index];
+        let mut flip = 0;
+        while flip < 2 {
+            let mut transpose = 0;
+            while transpose < 2 {
+                let mut cell = 0;
+                while cell < N_CELLS {
+                    let row = rows[cell / BOARD_SIZE];
+                    let mut column = rows[cell % BOARD_SIZE];
+                    if flip == 1 {
+                        column = BOARD_SIZE - 1 - column;
+                    }
+                    symmetries[count][cell] = if transpose == 1 {
+                        column * BOARD_SIZE + row
+                    } else {
+                        row * BOARD_SIZE + column
+                    };
+                    cell += 1;
+                }
+                count += 1;
+                transpose += 1;
+            }
+            flip += 1;
+        }
+        index += 1;
+    }
+    symmetries
+}
+
+const BOARD_SYMMETRIES: [[usize; N_CELLS]; N_BOARD_SYMMETRIES] = board_symmetries();
+
+// For every attribute permutation, the piece every piece is turned into
+const fn piece_permutations() -> [[u8; N_PIECES]; N_PERMUTATIONS] {
+    let mut tables = [[0; N_PIECES]; N_PERMUTATIONS];
+    let mut index = 0;
+    while index < N_PERMUTATIONS {
+        let mut piece = 0;
+        while piece < N_PIECES {
+            let mut attribute = 0;
+            while attribute < N_PROPERTIES {
+                if piece & (1 << attribute) != 0 {
+                    tables[index][piece] |= 1 << PERMUTATIONS[index][attribute];
+                }
+                attribute += 1;
+            }
+            piece += 1;
+        }
+        index += 1;
+    }
+    tables
+}
+
+const PIECE_PERMUTATIONS: [[u8; N_PIECES]; N_PERMUTATIONS] = piece_permutations();
+
+/// Returns the representative of every position equivalent to `game`: among the ones with the
+/// smallest mask of occupied cells, the one that comes first when comparing the pieces cell by
+/// cell. The history of `game` is not kept.
+pub fn canonical(game: &Game) -> Game {
+    let mut cells = [EMPTY; N_CELLS];
+    for (row_index, row) in game.board.grid.iter().enumerate() {
+        for (column_index, cell) in row.iter().enumerate() {
+            if let Some(piece) = cell {
+                cells[row_index * BOARD_SIZE + column_index] = piece_to_index(*piece);
+            }
+        }
+    }
+    let in_hand = match game.game_state.stage {
+        Stage::ChoosingPieceForOponent => None,
+        Stage::PlacingPieceGivenOponentChoice(piece) => Some(piece_to_index(piece)),
+    };
+    let (cells, in_hand) = canonical_cells(&cells, in_hand);
+
+    let mut board = Board::new();
+    let mut pieces_left = crate::game::all_pieces();
+    for (cell, piece) in cells.iter().enumerate() {
+        if *piece != EMPTY {
+            let piece = piece_from_index(*piece);
+            pieces_left.remove(&piece);
+            board.grid[cell / BOARD_SIZE][cell % BOARD_SIZE] = Some(piece);
+        }
+    }
+    let stage = match in_hand {
+        None => Stage::ChoosingPieceForOponent,
+        Some(piece) => {
+            let piece = piece_from_index(piece);
+            pieces_left.remove(&piece);
+            Stage::PlacingPieceGivenOponentChoice(piece)
+        }
+    };
+
+    Game::from_parts(
+        board,
+        GameState {
+            player_turn: game.game_state.player_turn,
+            stage,
+            result: game.game_state.result,
+        },
+        pieces_left,
+    )
+}
+
+// The canonical form of the index of the piece of every cell, `EMPTY` for none, and of the piece
+// in hand, see `canonical`
+pub(crate) fn canonical_cells(
+    cells: &[u8; N_CELLS],
+    in_hand: Option<u8>,
+) -> ([u8; N_CELLS], Option<u8>) {
+    // Candidates are compared by which cells are occupied first, so only the board symmetries
+    // leading to the smallest occupancy mask need to be tried with every piece transformation
+    let occupancy = |symmetry: &[usize; N_CELLS]| {
+        cells
+            .iter()
+            .zip(symmetry)
+            .filter(|(piece, _)| **piece != EMPTY)
+            .fold(0u16, |mask, (_, cell)| mask | 1 << cell)
+    };
+    let smallest_occupancy = BOARD_SYMMETRIES.iter().map(occupancy).min().unwrap_or(0);
+
+    // The piece in hand goes last so it's compared after the board
+    let mut best = [u8::MAX; N_CELLS + 1];
+    let mut transformed = [EMPTY; N_CELLS + 1];
+    for symmetry in BOARD_SYMMETRIES
+        .iter()
+        .filter(|symmetry| occupancy(symmetry) == smallest_occupancy)
+    {
+        for (cell, piece) in cells.iter().enumerate() {
+            transformed[symmetry[cell]] = *piece;
+        }
+        transformed[N_CELLS] = in_hand.unwrap_or(EMPTY);
+
+        // Inverting the attributes of the first piece turns it into the piece 0, which any
+        // permutation keeps as is. That leaves only the permutations to try
+        let inversion = transformed
+            .iter()
+            .find(|piece| **piece != EMPTY)
+            .copied()
+            .unwrap_or(0);
+
+        for table in PIECE_PERMUTATIONS {
+            // Most candidates are worse than the best one from the first few cells, so they are
+            // compared while they are built
+            let mut candidate = [EMPTY; N_CELLS + 1];
+            let mut ordering = Ordering::Equal;
+            for (index, piece) in transformed.iter().enumerate() {
+                if *piece != EMPTY {
+                    candidate[index] = table[(piece ^ inversion) as usize];
+                }
+                if ordering == Ordering::Equal {
+                    ordering = candidate[index].cmp(&best[index]);
+                    if ordering == Ordering::Greater {
+                        break;
+                    }
+                }
+            }
+            if ordering == Ordering::Less {
+                best = candidate;
+            }
+        }
+    }
+
+    let mut canonical_cells = [EMPTY; N_CELLS];
+    canonical_cells.copy_from_slice(&best[..N_CELLS]);
+    let in_hand = match best[N_CELLS] {
+        EMPTY => None,
+        piece => Some(piece),
+    };
+    (canonical_cells, in_hand)
+}
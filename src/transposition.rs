@@ -0,0 +1,138 @@
+//! Fixed-size memory of solved values, keyed by the hash of the state.
+
+use std::mem::size_of;
+
+#[derive(Copy, Clone, Debug, Default)]
+struct Entry {
+    key: u64,
+    value: i32,
+    // How much work the entry saves, entries saving more are kept longer
+    priority: u8,
+    used: bool,
+}
+
+// Entries are placed in the bucket of their key. The first entry of a bucket is the one with the
+// highest priority, the second one is just the latest stored
+type Bucket = [Entry; 2];
+
+/// A table of values with a fixed amount of memory. When the bucket of a new value is full, the
+/// value replaces the one that saves the least work: the entry with the lowest priority, or the
+/// oldest one if priorities are equal.
+///
+/// Different states can have the same key, the table doesn't tell them apart. With 64-bit keys
+/// that's unlikely enough to be ignored.
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    len: usize,
+}
+
+impl TranspositionTable {
+    /// Creates a table using at most `bytes` of memory, and at least one bucket.
+    pub fn with_memory(bytes: usize) -> TranspositionTable {
+        let buckets = (bytes / size_of::<Bucket>()).max(1);
+        // A power of two so the bucket is just the lowest bits of the key
+        let buckets = 1 << buckets.ilog2();
+        TranspositionTable {
+            buckets: vec![Bucket::default(); buckets],
+            len: 0,
+        }
+    }
+
+    /// How many values fit in the table.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * 2
+    }
+
+    /// How many values are stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no values are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn bucket(&self, key: u64) -> usize {
+        key as usize & (self.buckets.len() - 1)
+    }
+
+    /// The value stored for `key`, if it wasn't replaced.
+    pub fn get(&self, key: u64) -> Option<i32> {
+        self.buckets[self.bucket(key)]
+            .iter()
+            .find(|entry| entry.used && entry.key == key)
+            .map(|entry| entry.value)
+    }
+
+    /// Stores `value` for `key`, possibly replacing another value. The higher the `priority` the
+    /// longer the value is kept, e.g. the size of the search it saves.
+    pub fn insert(&mut self, key: u64, value: i32, priority: u8) {
+        let index = self.bucket(key);
+        let bucket = &mut self.buckets[index];
+        let entry = Entry {
+            key,
+            value,
+            priority,
+            used: true,
+        };
+
+        if let Some(existing) = bucket
+            .iter_mut()
+            .find(|existing| existing.used && existing.key == key)
+        {
+            *existing = entry;
+            return;
+        }
+
+        // Either the second entry is free or it's the one replaced
+        if !bucket[1].used {
+            self.len += 1;
+        }
+        if !bucket[0].used || priority >= bucket[0].priority {
+            bucket[1] = bucket[0];
+            bucket[0] = entry;
+        } else {
+            bucket[1] = entry;
+        }
+    }
+
+    /// Removes every value.
+    pub fn clear(&mut self) {
+        self.buckets.fill(Bucket::default());
+        self.len = 0;
+    }
+
+    /// Splits the table in `shards` tables with as many buckets each, `shards` being a power of
+    /// two no larger than the number of buckets. Shard `i` has the buckets of the keys whose
+    /// bucket in this table is in the `i`-th part, so a key is found in its shard as it would be
+    /// here, see [`TranspositionTable::shard`].
+    pub(crate) fn split(self, shards: usize) -> Vec<TranspositionTable> {
+        let buckets_per_shard = self.buckets.len() / shards;
+        self.buckets
+            .chunks(buckets_per_shard)
+            .map(|buckets| TranspositionTable {
+                buckets: buckets.to_vec(),
+                len: buckets.iter().flatten().filter(|entry| entry.used).count(),
+            })
+            .collect()
+    }
+
+    /// The shard of `key` among the tables a table with `buckets` buckets was split in.
+    pub(crate) fn shard(key: u64, buckets: usize, shards: usize) -> usize {
+        (key as usize & (buckets - 1)) / (buckets / shards)
+    }
+
+    /// The number of buckets, a power of two.
+    pub(crate) fn buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// The table split with [`TranspositionTable::split`].
+    pub(crate) fn join(shards: Vec<TranspositionTable>) -> TranspositionTable {
+        TranspositionTable {
+            len: shards.iter().map(|shard| shard.len).sum(),
+            buckets: shards.into_iter().flat_map(|shard| shard.buckets).collect(),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Zobrist hashing: every feature of a position gets a random 64-bit key, and the hash of a
+//! position is the XOR of the keys of its features.
+
+use crate::coordinate::Coordinate;
+use crate::game::{Game, Player, Stage};
+use crate::piece::{piece_to_index, Piece};
+use crate::random::SplitMix64;
+use crate::{BOARD_SIZE, N_PROPERTIES};
+
+const N_CELLS: usize = BOARD_SIZE * BOARD_SIZE;
+const N_PIECES: usize = 1 << N_PROPERTIES;
+
+// Fixed seed, so hashes are the same from run to run and can be stored
+const SEED: u64 = 0x5155_4152_544f_2121;
+
+struct Keys {
+    cells: [[u64; N_PIECES]; N_CELLS],
+    in_hand: [u64; N_PIECES],
+    player2: u64,
+}
+
+const fn keys() -> Keys {
+    let mut rng = SplitMix64::new(SEED);
+    let mut keys = Keys {
+        cells: [[0; N_PIECES]; N_CELLS],
+        in_hand: [0; N_PIECES],
+        player2: 0,
+    };
+    let mut piece = 0;
+    while piece < N_PIECES {
+        let mut cell = 0;
+        while cell < N_CELLS {
+            keys.cells[cell][piece] = rng.next_u64();
+            cell += 1;
+        }
+        keys.in_hand[piece] = rng.next_u64();
+        piece += 1;
+    }
+    keys.player2 = rng.next_u64();
+    keys
+}
+
+const KEYS: Keys = keys();
+
+/// Hash of the pieces on the board, the piece waiting to be placed and the player to move,
+/// computed from scratch. The result of the game is not part of it, it follows from the board.
+/// [`Game::key`] keeps the same hash up to date as actions are played.
+pub fn hash(game: &Game) -> u64 {
+    let cells = game.board.grid.iter().flatten().enumerate();
+    let in_hand = match game.game_state.stage {
+        Stage::ChoosingPieceForOponent => None,
+        Stage::PlacingPieceGivenOponentChoice(piece) => Some(piece_to_index(piece)),
+    };
+    hash_cells(
+        cells.filter_map(|(cell, piece)| Some((cell, piece_to_index((*piece)?)))),
+        in_hand,
+        game.game_state.player_turn,
+    )
+}
+
+// The hash from the index of the piece of every occupied cell, cells numbered row by row
+pub(crate) fn hash_cells(
+    cells: impl Iterator<Item = (usize, u8)>,
+    in_hand: Option<u8>,
+    player: Player,
+) -> u64 {
+    let mut hash = in_hand_key(in_hand) ^ player_key(player);
+    for (cell, piece) in cells {
+        hash ^= cell_index_key(cell, piece);
+    }
+    hash
+}
+
+pub(crate) fn cell_key(position: Coordinate, piece: Piece) -> u64 {
+    cell_index_key(
+        position.row * BOARD_SIZE + position.column,
+        piece_to_index(piece),
+    )
+}
+
+// The key of the piece of index `piece` at `cell`, cells numbered row by row
+pub(crate) fn cell_index_key(cell: usize, piece: u8) -> u64 {
+    KEYS.cells[cell][piece as usize]
+}
+
+pub(crate) fn stage_key(stage: Stage) -> u64 {
+    match stage {
+        Stage::ChoosingPieceForOponent => in_hand_key(None),
+        Stage::PlacingPieceGivenOponentChoice(piece) => in_hand_key(Some(piece_to_index(piece))),
+    }
+}
+
+// The key of the piece of index `piece` waiting to be placed, if any
+pub(crate) fn in_hand_key(piece: Option<u8>) -> u64 {
+    piece.map_or(0, |piece| KEYS.in_hand[piece as usize])
+}
+
+pub(crate) fn player_key(player: Player) -> u64 {
+    match player {
+        Player::Player1 => 0,
+        Player::Player2 => KEYS.player2,
+    }
+}
@@ -0,0 +1,45 @@
+//! Opening books written from a memory and looked up from the file.
+
+use quatro_in_rust::{canonical, Book};
+
+use std::collections::HashMap;
+use std::io;
+
+mod common;
+
+use common::{game, overflow_entry_count, temporary_path, values};
+
+#[test]
+fn written_book_finds_equivalent_positions() {
+    let path = temporary_path("round-trip.qbk");
+    let memory = values();
+    assert_eq!(Book::write(&memory, &path).unwrap(), 3);
+
+    let book = Book::open(&path).unwrap();
+    assert_eq!(book.len(), 3);
+    for (game, value) in &memory {
+        assert_eq!(book.get(game), Some(*value));
+    }
+
+    // The first piece in another corner, which is not the canonical position
+    let mirrored = game("...0/..../..../.... - 2 *");
+    assert_ne!(canonical(&mirrored), mirrored);
+    assert_eq!(book.get(&mirrored), Some(-3));
+    assert_eq!(book.get(&game("..../..../..../...0 - 2 *")), Some(-3));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn book_with_wrong_entry_count_is_rejected() {
+    let path = temporary_path("entries.qbk");
+    Book::write(
+        &HashMap::from([(game("..../..../..../.... - 1 *"), 0)]),
+        &path,
+    )
+    .unwrap();
+    overflow_entry_count(&path);
+
+    let error = Book::open(&path).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    std::fs::remove_file(path).unwrap();
+}
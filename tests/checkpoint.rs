@@ -0,0 +1,66 @@
+//! Long solves interrupted at a checkpoint and resumed from what it saved.
+
+use quatro_in_rust::checkpoint::solve_with_checkpoints;
+use quatro_in_rust::{Game, Minimax, QuartoMinimax};
+
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+#[test]
+fn interrupted_solve_resumes_to_the_same_value() {
+    // Thousands of states to search, with 6 pieces left
+    let game: Game = "4..9/..da/7.e3/f.8. 2 1 *".parse().unwrap();
+    let mut searched_at_once = 0;
+    let expected = solve_with_checkpoints(
+        &mut QuartoMinimax::new(HashMap::new()),
+        &mut HashMap::new(),
+        &game,
+        Duration::MAX,
+        |_, _, progress| {
+            searched_at_once = progress.searched;
+            Ok(())
+        },
+    )
+    .unwrap();
+    assert_eq!(QuartoMinimax::new(HashMap::new()).value(&game), expected);
+
+    // A checkpoint after every state, stopped halfway as if the process was killed right after
+    // saving it
+    let mut checkpoints = 0;
+    let mut saved = None;
+    let error = solve_with_checkpoints(
+        &mut QuartoMinimax::new(HashMap::new()),
+        &mut HashMap::new(),
+        &game,
+        Duration::ZERO,
+        |solver, bounds, _| {
+            checkpoints += 1;
+            if checkpoints < searched_at_once / 2 {
+                return Ok(());
+            }
+            saved = Some((solver.memory(), bounds.clone()));
+            Err(io::Error::other("interrupted"))
+        },
+    )
+    .unwrap_err();
+    assert_eq!(error.to_string(), "interrupted");
+
+    let (memory, mut bounds) = saved.unwrap();
+    assert!(!memory.is_empty());
+    let mut searched_after_resuming = 0;
+    let value = solve_with_checkpoints(
+        &mut QuartoMinimax::new(memory),
+        &mut bounds,
+        &game,
+        Duration::MAX,
+        |_, _, progress| {
+            searched_after_resuming = progress.searched;
+            Ok(())
+        },
+    )
+    .unwrap();
+
+    assert_eq!(value, expected, "{}", game.to_notation());
+    assert!(searched_after_resuming < searched_at_once);
+}
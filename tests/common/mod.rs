@@ -0,0 +1,36 @@
+//! Fixtures shared by the tests of the files written by the solver.
+
+// Each test crate only uses some of them
+#![allow(dead_code)]
+
+use quatro_in_rust::Game;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// A path in the temporary directory only used by the test called `name`
+pub fn temporary_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("quatro-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+pub fn game(notation: &str) -> Game {
+    notation.parse().unwrap()
+}
+
+// A few positions of the opening with made up values
+pub fn values() -> HashMap<Game, i32> {
+    HashMap::from([
+        (game("..../..../..../.... - 1 *"), 0),
+        (game("0.../..../..../.... - 2 *"), -3),
+        (game("0.../.1../..../.... 2 2 *"), 5),
+    ])
+}
+
+// Claims so many entries in the header of the file at `path` that their size overflows
+pub fn overflow_entry_count(path: &Path) {
+    let mut bytes = std::fs::read(path).unwrap();
+    bytes[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+    std::fs::write(path, bytes).unwrap();
+}
@@ -0,0 +1,193 @@
+//! Database files: what they record about themselves and how damaged ones are rejected.
+
+use quatro_in_rust::{
+    is_legacy_database, migrate_legacy_values, read_database, read_header, read_legacy_database,
+    write_database, DatabaseFormat, Game, QuartoMinimax, SymmetryMode,
+};
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+mod common;
+
+use common::{game, overflow_entry_count, temporary_path, values};
+
+fn memory() -> HashMap<Game, i32> {
+    let mut memory = values();
+    memory.insert(game("..../..../..../.... 0 2 *"), 0);
+    memory
+}
+
+#[test]
+fn compact_round_trip_records_the_symmetry_mode() {
+    let path = temporary_path("round-trip.qdb");
+    let memory = memory();
+    for symmetries in [SymmetryMode::Positions, SymmetryMode::Canonical] {
+        write_database(&memory, &path, DatabaseFormat::Compact, symmetries).unwrap();
+        let header = read_header(&path).unwrap();
+        assert_eq!(header.symmetries, symmetries);
+        assert_eq!(header.entries, memory.len() as u64);
+        assert_eq!(
+            read_database(&path, DatabaseFormat::Compact).unwrap(),
+            memory
+        );
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn corrupt_compact_database_is_rejected() {
+    let path = temporary_path("corrupt.qdb");
+    write_database(
+        &memory(),
+        &path,
+        DatabaseFormat::Compact,
+        SymmetryMode::Positions,
+    )
+    .unwrap();
+    let mut bytes = std::fs::read(&path).unwrap();
+    // A bit of the value of the last entry
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0x10;
+    std::fs::write(&path, bytes).unwrap();
+
+    let error = read_database(&path, DatabaseFormat::Compact).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("checksum"), "{error}");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn compact_database_with_wrong_entry_count_is_rejected() {
+    let path = temporary_path("entries.qdb");
+    write_database(
+        &memory(),
+        &path,
+        DatabaseFormat::Compact,
+        SymmetryMode::Positions,
+    )
+    .unwrap();
+    overflow_entry_count(&path);
+
+    let error = read_database(&path, DatabaseFormat::Compact).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn old_compact_version_is_unsupported() {
+    let path = temporary_path("old.qdb");
+    write_database(
+        &memory(),
+        &path,
+        DatabaseFormat::Compact,
+        SymmetryMode::Positions,
+    )
+    .unwrap();
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[8..10].copy_from_slice(&1u16.to_le_bytes());
+    std::fs::write(&path, bytes).unwrap();
+
+    for error in [
+        read_database(&path, DatabaseFormat::Compact).unwrap_err(),
+        read_header(&path).unwrap_err(),
+    ] {
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn json_and_bincode_round_trip() {
+    let memory = memory();
+    for (name, format) in [
+        ("round-trip.json", DatabaseFormat::Json),
+        ("round-trip.bin", DatabaseFormat::Bincode),
+    ] {
+        let path = temporary_path(name);
+        write_database(&memory, &path, format, SymmetryMode::Positions).unwrap();
+        assert_eq!(DatabaseFormat::detect(&path).unwrap(), format);
+        assert_eq!(read_database(&path, format).unwrap(), memory);
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+// Values of files of the first version, which had no version
+fn legacy_values() -> HashMap<Game, i32> {
+    [
+        ("d3a0/42ef/7b96/1c.5 8 1 *", 1),
+        ("2bd./5a04/87f3/61ec 9 1 *", 0),
+        ("d3a./42e./7b96/1c.5 - 2 *", 1),
+    ]
+    .into_iter()
+    .map(|(notation, value)| (game(notation), value))
+    .collect()
+}
+
+fn write_legacy(path: &PathBuf, format: DatabaseFormat, values: &HashMap<Game, i32>) {
+    let contents = match format {
+        DatabaseFormat::Json => serde_json::to_vec(
+            &values
+                .iter()
+                .map(|(game, value)| (serde_json::to_string(game).unwrap(), *value))
+                .collect::<HashMap<_, _>>(),
+        )
+        .unwrap(),
+        _ => bincode::serialize(values).unwrap(),
+    };
+    std::fs::write(path, contents).unwrap();
+}
+
+#[test]
+fn unversioned_json_and_bincode_are_migrated() {
+    for (name, format) in [
+        ("old.json", DatabaseFormat::Json),
+        ("old.bin", DatabaseFormat::Bincode),
+    ] {
+        let path = temporary_path(name);
+        write_legacy(&path, format, &legacy_values());
+
+        let error = read_database(&path, format).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported, "{error}");
+        assert!(is_legacy_database(&path, format).unwrap());
+        let legacy = read_legacy_database(&path, format).unwrap();
+        assert_eq!(legacy, legacy_values());
+
+        let mut solver = QuartoMinimax::new(HashMap::new());
+        migrate_legacy_values(&mut solver, &legacy).unwrap();
+        // The wins now tell how soon they happen
+        for (game, legacy_value) in legacy {
+            let value = solver.known_value(&game).unwrap();
+            assert_eq!(value.signum(), legacy_value, "{}", game.to_notation());
+        }
+        assert_eq!(
+            solver.known_value(&game("d3a0/42ef/7b96/1c.5 8 1 *")),
+            Some(1)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn versioned_files_are_not_legacy() {
+    for (name, format) in [
+        ("new.json", DatabaseFormat::Json),
+        ("new.bin", DatabaseFormat::Bincode),
+        ("new.qdb", DatabaseFormat::Compact),
+    ] {
+        let path = temporary_path(name);
+        write_database(&memory(), &path, format, SymmetryMode::Positions).unwrap();
+        assert!(!is_legacy_database(&path, format).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn migration_rejects_wins_of_the_wrong_player() {
+    let mut legacy = legacy_values();
+    legacy.insert(game("d3a0/42ef/7b96/1c.5 8 1 *"), -1);
+    let mut solver = QuartoMinimax::new(HashMap::new());
+    let error = migrate_legacy_values(&mut solver, &legacy).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
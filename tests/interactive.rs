@@ -0,0 +1,28 @@
+//! Games played on the terminal.
+
+use quatro_in_rust::interactive::{play, Controller, Search};
+use quatro_in_rust::QuartoMinimax;
+
+use std::collections::HashMap;
+
+#[test]
+fn moves_are_typed_as_in_game_records() {
+    let mut qmm = QuartoMinimax::new(HashMap::new());
+    let mut output = Vec::new();
+    let result = play(
+        [Controller::Human, Controller::Human],
+        Search::Exhaustive,
+        &mut qmm,
+        "0\na1\n0110\n1 1\nf\n".as_bytes(),
+        &mut output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert_eq!(result, None);
+    assert!(output.contains("Pieces left: 0 1 2 3 4 5 6 7 8 9 a b c d e f\n"));
+    assert!(output.contains("Pieces left: 1 2 3 4 5 6 7 8 9 a b c d e f\n"));
+    assert!(output.contains("Couldn't understand \"0110\""));
+    assert!(output.contains("Couldn't understand \"1 1\""));
+    assert!(output.contains("Player1, place f"));
+}
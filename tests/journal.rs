@@ -0,0 +1,138 @@
+//! Recovery of the journal after the process was killed while writing it.
+
+use quatro_in_rust::{Game, Journal};
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+mod common;
+
+use common::{game, temporary_path, values};
+
+// Writes `values` to a new journal at `path`, returning the length of the file
+fn write_journal(path: &PathBuf, values: &HashMap<Game, i32>) -> u64 {
+    let (mut journal, logged) = Journal::open(path).unwrap();
+    assert!(logged.is_empty());
+    for (game, value) in values {
+        journal.append(game, *value);
+    }
+    journal.sync().unwrap();
+    std::fs::metadata(path).unwrap().len()
+}
+
+#[test]
+fn values_survive_reopening() {
+    let path = temporary_path("reopen.log");
+    let length = write_journal(&path, &values());
+
+    assert_eq!(Journal::read(&path).unwrap(), values());
+    let (_, logged) = Journal::open(&path).unwrap();
+    assert_eq!(logged, values());
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), length);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn truncated_last_record_is_dropped() {
+    let path = temporary_path("truncated.log");
+    let valid_length = write_journal(&path, &values());
+    let (mut journal, _) = Journal::open(&path).unwrap();
+    journal.append(&game("0.../.1../2.../.... - 2 *"), 7);
+    journal.sync().unwrap();
+    drop(journal);
+    let length = std::fs::metadata(&path).unwrap().len();
+    // Killed in the middle of the last record
+    for cut in [valid_length + 3, (valid_length + length) / 2, length - 1] {
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(cut).unwrap();
+        drop(file);
+
+        assert_eq!(Journal::read(&path).unwrap(), values());
+        let (_, logged) = Journal::open(&path).unwrap();
+        assert_eq!(logged, values());
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), valid_length);
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn corrupt_last_record_is_dropped() {
+    let path = temporary_path("corrupt.log");
+    let valid_length = write_journal(&path, &values());
+    let (mut journal, _) = Journal::open(&path).unwrap();
+    journal.append(&game("0.../.1../2.../.... - 2 *"), 7);
+    journal.sync().unwrap();
+    drop(journal);
+    let mut contents = std::fs::read(&path).unwrap();
+    let last = contents.len() - 1;
+    contents[last] ^= 1;
+    std::fs::write(&path, contents).unwrap();
+
+    let (mut journal, logged) = Journal::open(&path).unwrap();
+    assert_eq!(logged, values());
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), valid_length);
+
+    // New values go after the valid ones
+    journal.append(&game("0.../.1../2.../.... - 2 *"), 9);
+    journal.sync().unwrap();
+    let mut expected = values();
+    expected.insert(game("0.../.1../2.../.... - 2 *"), 9);
+    assert_eq!(Journal::read(&path).unwrap(), expected);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cleared_journal_is_empty() {
+    let path = temporary_path("clear.log");
+    write_journal(&path, &values());
+    let (mut journal, _) = Journal::open(&path).unwrap();
+    journal.clear().unwrap();
+    drop(journal);
+
+    assert!(Journal::read(&path).unwrap().is_empty());
+    assert!(Journal::open(&path).unwrap().1.is_empty());
+    std::fs::remove_file(path).unwrap();
+}
+
+// Journals of the first version had no header
+#[test]
+fn unversioned_journal_is_unsupported() {
+    let path = temporary_path("old.log");
+    std::fs::write(&path, [4, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+
+    let error = Journal::read(&path).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    let error = Journal::open(&path).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    // Nothing was removed
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 12);
+    std::fs::remove_file(path).unwrap();
+}
+
+// Logs of version 2 had bincode records, they are still read and rewritten in the current version
+#[test]
+fn bincode_journal_is_rewritten() {
+    let path = temporary_path("bincode.log");
+    let mut contents = b"QUARTOLG".to_vec();
+    contents.extend_from_slice(&2u16.to_le_bytes());
+    for (game, value) in values() {
+        let payload = bincode::serialize(&(game, value)).unwrap();
+        contents.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        contents.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        contents.extend_from_slice(&payload);
+    }
+    std::fs::write(&path, &contents).unwrap();
+
+    assert_eq!(Journal::read(&path).unwrap(), values());
+    let (mut journal, logged) = Journal::open(&path).unwrap();
+    assert_eq!(logged, values());
+    assert_ne!(std::fs::read(&path).unwrap(), contents);
+
+    journal.append(&game("0.../.1../2.../.... - 2 *"), 9);
+    journal.sync().unwrap();
+    let mut expected = values();
+    expected.insert(game("0.../.1../2.../.... - 2 *"), 9);
+    assert_eq!(Journal::read(&path).unwrap(), expected);
+    std::fs::remove_file(path).unwrap();
+}
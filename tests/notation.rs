@@ -0,0 +1,64 @@
+//! Parsing positions written in the notation.
+
+use quatro_in_rust::{Game, NotationError};
+
+#[test]
+fn positions_round_trip() {
+    for notation in [
+        "..../..../..../.... - 1 *",
+        "0.../.3../..f./.... a 1 *",
+        "d3a0/42ef/7b96/1c.5 8 1 *",
+        "d3a0/42ef/7b96/1c85 8 1 1",
+        "2bd9/5a04/87f3/61ec 9 1 =",
+    ] {
+        let game = Game::from_notation(notation).unwrap();
+        assert_eq!(game.to_notation(), notation);
+    }
+}
+
+#[test]
+fn inconsistent_positions_are_rejected() {
+    for notation in [
+        // Full board in progress, with nothing left to play
+        "0123/4567/89ab/cdef - 1 *",
+        // Won without a piece placed last, or by a piece that doesn't complete a line
+        "0123/4567/89ab/cde. - 2 1",
+        "2bd9/5a04/87f3/61ec 9 1 2",
+        // Finished with a piece in hand that was never placed
+        "d3a0/42ef/7b96/1c.. 8 1 1",
+        // In progress with a complete line
+        "0123/..../..../.... - 1 *",
+        // Draw with a complete line or with empty cells
+        "0123/4567/89ab/cdef f 2 =",
+        "2bd./5a04/87f3/61ec 9 1 =",
+        // The player to move doesn't match the pieces chosen
+        "..../..../..../.... - 2 *",
+        "..../..../..../.... 0 1 *",
+        "0.../..../..../.... - 1 *",
+        // Won by the player who didn't place the last piece
+        "d3a0/42ef/7b96/1c85 8 1 2",
+        "d3a0/42ef/7b96/1c85 8 2 2",
+    ] {
+        assert!(
+            matches!(
+                Game::from_notation(notation),
+                Err(NotationError::Inconsistent(_))
+            ),
+            "{notation}"
+        );
+    }
+}
+
+#[test]
+fn positions_reached_by_playing_are_consistent() {
+    let mut game = Game::new();
+    while let Some(&action) = game.legal_actions().first() {
+        game.play(action).unwrap();
+        let notation = game.to_notation();
+        assert_eq!(
+            Game::from_notation(&notation),
+            Ok(game.clone()),
+            "{notation}"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Invariants of the engine checked on random games, comparing the grid representation with the
+//! bit mask one along the way.
+
+use quatro_in_rust::bitboard::BitGame;
+use quatro_in_rust::zobrist;
+use quatro_in_rust::{Coordinate, Game, GameResult, Piece, QuartoAction, QuartoError, Stage};
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use std::collections::HashSet;
+
+const BOARD_SIZE: usize = 4;
+
+fn piece(index: usize) -> Piece {
+    [
+        index & 8 != 0,
+        index & 4 != 0,
+        index & 2 != 0,
+        index & 1 != 0,
+    ]
+}
+
+fn all_pieces() -> Vec<Piece> {
+    (0..16).map(piece).collect()
+}
+
+fn all_cells() -> impl Iterator<Item = Coordinate> {
+    (0..BOARD_SIZE).flat_map(|row| (0..BOARD_SIZE).map(move |column| Coordinate::new(row, column)))
+}
+
+// Every row, column and diagonal going through `position`
+fn lines_through(position: Coordinate) -> Vec<Vec<Coordinate>> {
+    let mut lines = vec![
+        (0..BOARD_SIZE)
+            .map(|column| Coordinate::new(position.row, column))
+            .collect(),
+        (0..BOARD_SIZE)
+            .map(|row| Coordinate::new(row, position.column))
+            .collect(),
+    ];
+    if position.row == position.column {
+        lines.push((0..BOARD_SIZE).map(|i| Coordinate::new(i, i)).collect());
+    }
+    if position.row + position.column == BOARD_SIZE - 1 {
+        lines.push(
+            (0..BOARD_SIZE)
+                .map(|i| Coordinate::new(i, BOARD_SIZE - 1 - i))
+                .collect(),
+        );
+    }
+    lines
+}
+
+// Whether a full line through `position` has pieces sharing an attribute, scanning the cells
+fn brute_force_won(game: &Game, position: Coordinate) -> bool {
+    lines_through(position).into_iter().any(|line| {
+        let pieces = line
+            .into_iter()
+            .map(|cell| game.board().get(cell).expect("inside the board"))
+            .collect::<Option<Vec<_>>>();
+        pieces.is_some_and(|pieces| {
+            (0..4).any(|attribute| {
+                pieces
+                    .iter()
+                    .all(|piece| piece[attribute] == pieces[0][attribute])
+            })
+        })
+    })
+}
+
+fn pieces_conserved(game: &Game) -> Result<(), TestCaseError> {
+    let mut pieces = all_cells()
+        .filter_map(|cell| game.board().get(cell).expect("inside the board"))
+        .collect::<Vec<_>>();
+    pieces.extend(game.get_pieces_left());
+    // Once the game is over the piece in hand is the last one placed
+    if let (GameResult::InProgress, Stage::PlacingPieceGivenOponentChoice(piece)) =
+        (game.result(), game.stage())
+    {
+        pieces.push(*piece);
+    }
+    pieces.sort();
+    prop_assert_eq!(pieces, all_pieces());
+    Ok(())
+}
+
+fn same_game(game: &Game, bit_game: &BitGame) -> Result<(), TestCaseError> {
+    prop_assert_eq!(*game.result(), bit_game.result());
+    prop_assert_eq!(*game.stage(), bit_game.stage());
+    prop_assert_eq!(game.player_turn(), bit_game.player_turn());
+    for cell in all_cells() {
+        prop_assert_eq!(game.board().get(cell), bit_game.get(cell));
+        prop_assert_eq!(game.check_if_won(cell), bit_game.check_if_won(cell));
+    }
+    let mut pieces_left = game.get_pieces_left();
+    pieces_left.sort();
+    prop_assert_eq!(pieces_left, bit_game.get_pieces_left());
+    prop_assert_eq!(BitGame::from(game), *bit_game);
+    prop_assert_eq!(bit_game.to_game().to_notation(), game.to_notation());
+    Ok(())
+}
+
+fn rejects_everything(game: &mut Game, bit_game: &mut BitGame) -> Result<(), TestCaseError> {
+    let over = Err(QuartoError::GameOver(*game.result()));
+    let notation = game.to_notation();
+    for piece in all_pieces() {
+        prop_assert_eq!(game.choose(piece), over.clone());
+        prop_assert_eq!(bit_game.choose(piece), over.clone());
+    }
+    for cell in all_cells() {
+        prop_assert_eq!(game.put(cell), over.clone());
+        prop_assert_eq!(bit_game.put(cell), over.clone());
+    }
+    prop_assert_eq!(game.to_notation(), notation);
+    Ok(())
+}
+
+// Everything the rules and the solver look at, including what `PartialEq` ignores
+fn same_state(game: &Game, expected: &Game) -> Result<(), TestCaseError> {
+    prop_assert_eq!(game, expected);
+    prop_assert_eq!(*game.result(), *expected.result());
+    let pieces_left = |game: &Game| game.get_pieces_left().into_iter().collect::<HashSet<_>>();
+    prop_assert_eq!(pieces_left(game), pieces_left(expected));
+    prop_assert_eq!(game.history(), expected.history());
+    prop_assert_eq!(game.key(), expected.key());
+    prop_assert_eq!(game.key(), zobrist::hash(game));
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    // A game never lasts more than 32 actions, so every game is played to the end
+    #[test]
+    fn random_games_keep_the_invariants(choices in prop::collection::vec(any::<Index>(), 32)) {
+        let mut game = Game::new();
+        let mut bit_game = BitGame::new();
+
+        for choice in choices {
+            if *game.result() != GameResult::InProgress {
+                break;
+            }
+
+            let player = game.player_turn();
+            match *game.stage() {
+                Stage::ChoosingPieceForOponent => {
+                    let piece = *choice.get(&game.get_pieces_left());
+                    prop_assert_eq!(game.choose(piece), Ok(()));
+                    prop_assert_eq!(bit_game.choose(piece), Ok(()));
+
+                    prop_assert_eq!(*game.stage(), Stage::PlacingPieceGivenOponentChoice(piece));
+                    prop_assert_eq!(game.player_turn(), player.opponent());
+                }
+                Stage::PlacingPieceGivenOponentChoice(piece) => {
+                    let position = *choice.get(&game.get_empty_places());
+                    prop_assert_eq!(game.put(position), Ok(()));
+                    prop_assert_eq!(bit_game.put(position), Ok(()));
+
+                    prop_assert_eq!(game.board().get(position), Ok(Some(piece)));
+                    let won = brute_force_won(&game, position);
+                    let expected = if won {
+                        GameResult::PlayerWon(player)
+                    } else if game.get_empty_places().is_empty() {
+                        GameResult::Draw
+                    } else {
+                        GameResult::InProgress
+                    };
+                    prop_assert_eq!(*game.result(), expected);
+                    if expected == GameResult::InProgress {
+                        prop_assert_eq!(*game.stage(), Stage::ChoosingPieceForOponent);
+                        prop_assert_eq!(game.player_turn(), player);
+                    }
+                }
+            }
+
+            for cell in all_cells() {
+                prop_assert_eq!(game.check_if_won(cell), brute_force_won(&game, cell));
+            }
+            pieces_conserved(&game)?;
+            same_game(&game, &bit_game)?;
+        }
+
+        prop_assert_ne!(*game.result(), GameResult::InProgress);
+        prop_assert!(game.legal_actions().is_empty());
+        rejects_everything(&mut game, &mut bit_game)?;
+    }
+
+    // Actions are drawn from every piece and from cells inside and outside the board, so most of
+    // them are illegal. Both representations must accept and reject the same ones
+    #[test]
+    fn both_representations_agree_on_any_action(
+        actions in prop::collection::vec((any::<bool>(), 0..16usize, 0..5usize, 0..5usize), 0..100)
+    ) {
+        let mut game = Game::new();
+        let mut bit_game = BitGame::new();
+
+        for (choose, piece_index, row, column) in actions {
+            let action = match choose {
+                true => QuartoAction::Choose(piece(piece_index)),
+                false => QuartoAction::Put(Coordinate::new(row, column)),
+            };
+            prop_assert_eq!(game.play(action), bit_game.play(action), "{:?}", action);
+            pieces_conserved(&game)?;
+            same_game(&game, &bit_game)?;
+        }
+    }
+
+    // The keys kept up to date by both representations are the ones computed from scratch. The
+    // bit masks are copied instead of undone, so they go back to an earlier copy
+    #[test]
+    fn incremental_keys_match_the_full_hash(
+        steps in prop::collection::vec((any::<bool>(), any::<Index>()), 0..60)
+    ) {
+        let mut game = Game::new();
+        let mut bit_games = vec![BitGame::new()];
+
+        for (undo, choice) in steps {
+            let legal_actions = game.legal_actions();
+            if undo || legal_actions.is_empty() {
+                if game.undo().is_some() {
+                    bit_games.pop();
+                }
+            } else {
+                let action = *choice.get(&legal_actions);
+                game.play(action).unwrap();
+                let mut bit_game = *bit_games.last().unwrap();
+                bit_game.play(action).unwrap();
+                bit_games.push(bit_game);
+            }
+
+            let bit_game = bit_games.last().unwrap();
+            prop_assert_eq!(game.key(), zobrist::hash(&game));
+            prop_assert_eq!(bit_game.key(), zobrist::hash(&bit_game.to_game()));
+            prop_assert_eq!(bit_game.key(), game.key());
+            prop_assert_eq!(bit_game.canonical().key(), bit_game.canonical().to_game().key());
+        }
+    }
+
+    // Undoing an action goes back to the state before it, result and key included, and redoing it
+    // to the state after it
+    #[test]
+    fn undo_and_redo_restore_the_exact_state(choices in prop::collection::vec(any::<Index>(), 32)) {
+        let mut game = Game::new();
+
+        for choice in choices {
+            let legal_actions = game.legal_actions();
+            if legal_actions.is_empty() {
+                break;
+            }
+            let before = game.clone();
+            let action = *choice.get(&legal_actions);
+            game.play(action).unwrap();
+            let after = game.clone();
+
+            prop_assert_eq!(game.undo(), Some(action));
+            same_state(&game, &before)?;
+            prop_assert_eq!(game.redo(), Some(action));
+            same_state(&game, &after)?;
+        }
+
+        // The whole game can be taken back and played again
+        let finished = game.clone();
+        while game.undo().is_some() {}
+        same_state(&game, &Game::new())?;
+        while game.redo().is_some() {}
+        same_state(&game, &finished)?;
+    }
+}
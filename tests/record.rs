@@ -0,0 +1,79 @@
+//! Game records written and read back, and records whose result contradicts their actions.
+
+use quatro_in_rust::record::{GameRecord, RecordError};
+use quatro_in_rust::{Game, GameResult, Player};
+
+// The game where the first legal action is always played, until it ends or after `actions` actions
+fn first_actions(actions: usize) -> GameRecord {
+    let mut game = Game::new();
+    while game.history().len() < actions {
+        let Some(action) = game.legal_actions().first().copied() else {
+            break;
+        };
+        game.play(action).unwrap();
+    }
+    GameRecord::new(game.history().to_vec())
+}
+
+#[test]
+fn records_survive_a_round_trip() {
+    for actions in [0, 1, 7, 32] {
+        let mut record = first_actions(actions);
+        record.set("Player1", "Alice \"A\"");
+        record.set("Date", "2022-09-20");
+        let written = record.to_string();
+        let read: GameRecord = written.parse().unwrap();
+        assert_eq!(read, record, "{written}");
+        assert_eq!(read.to_string(), written);
+    }
+}
+
+#[test]
+fn finished_game_ends_with_its_result() {
+    let record = first_actions(32);
+    let result = *record.final_position().unwrap().result();
+    assert_ne!(result, GameResult::InProgress);
+    let written = record.to_string();
+    assert!(!written.trim_end().ends_with('*'), "{written}");
+}
+
+#[test]
+fn wrong_result_is_rejected() {
+    let in_progress = first_actions(7).to_string();
+    let claimed_won = in_progress.replace(" *", " 1-0");
+    assert_eq!(
+        claimed_won.parse::<GameRecord>(),
+        Err(RecordError::WrongResult {
+            written: GameResult::PlayerWon(Player::Player1),
+            actual: GameResult::InProgress,
+        })
+    );
+
+    let finished = first_actions(32);
+    let actual = *finished.final_position().unwrap().result();
+    let written = finished.to_string();
+    let (actions, _) = written.trim_end().rsplit_once(' ').unwrap();
+    // Only the result the game ended with is accepted
+    let accepted = ["*", "1-0", "0-1", "1/2-1/2"]
+        .into_iter()
+        .filter(
+            |result| match format!("{actions} {result}").parse::<GameRecord>() {
+                Ok(record) => record == finished,
+                Err(error) => {
+                    assert!(
+                        matches!(error, RecordError::WrongResult { actual: a, .. } if a == actual),
+                        "{error}"
+                    );
+                    false
+                }
+            },
+        )
+        .count();
+    assert_eq!(accepted, 1);
+}
+
+#[test]
+fn record_without_result_is_not_checked() {
+    let record: GameRecord = "1. 0 a1 2. 5 b2".parse().unwrap();
+    assert_eq!(record.actions.len(), 4);
+}
@@ -0,0 +1,308 @@
+//! The rules of Quarto, checked on both game representations.
+
+use quatro_in_rust::bitboard::BitGame;
+use quatro_in_rust::{Coordinate, Game, GameResult, Piece, Player, QuartoAction, QuartoError};
+
+use QuartoAction::{Choose, Put};
+
+/// What the rules need from a game representation.
+trait Engine: Sized {
+    fn start() -> Self;
+    fn from_notation(notation: &str) -> Self;
+    fn play(&mut self, action: QuartoAction) -> Result<(), QuartoError>;
+    fn result(&self) -> GameResult;
+    fn player_turn(&self) -> Player;
+    fn get(&self, position: Coordinate) -> Result<Option<Piece>, QuartoError>;
+}
+
+impl Engine for Game {
+    fn start() -> Self {
+        Game::new()
+    }
+
+    fn from_notation(notation: &str) -> Self {
+        notation.parse().expect("valid notation")
+    }
+
+    fn play(&mut self, action: QuartoAction) -> Result<(), QuartoError> {
+        Game::play(self, action)
+    }
+
+    fn result(&self) -> GameResult {
+        *Game::result(self)
+    }
+
+    fn player_turn(&self) -> Player {
+        Game::player_turn(self)
+    }
+
+    fn get(&self, position: Coordinate) -> Result<Option<Piece>, QuartoError> {
+        self.board().get(position)
+    }
+}
+
+impl Engine for BitGame {
+    fn start() -> Self {
+        BitGame::new()
+    }
+
+    fn from_notation(notation: &str) -> Self {
+        BitGame::from(&<Game as Engine>::from_notation(notation))
+    }
+
+    fn play(&mut self, action: QuartoAction) -> Result<(), QuartoError> {
+        BitGame::play(self, action)
+    }
+
+    fn result(&self) -> GameResult {
+        BitGame::result(self)
+    }
+
+    fn player_turn(&self) -> Player {
+        BitGame::player_turn(self)
+    }
+
+    fn get(&self, position: Coordinate) -> Result<Option<Piece>, QuartoError> {
+        BitGame::get(self, position)
+    }
+}
+
+// Runs the check on both representations
+macro_rules! on_every_engine {
+    ($check:ident) => {
+        $check::<Game>();
+        $check::<BitGame>();
+    };
+}
+
+const ROWS: [[(usize, usize); 4]; 4] = [
+    [(0, 0), (0, 1), (0, 2), (0, 3)],
+    [(1, 0), (1, 1), (1, 2), (1, 3)],
+    [(2, 0), (2, 1), (2, 2), (2, 3)],
+    [(3, 0), (3, 1), (3, 2), (3, 3)],
+];
+const COLUMNS: [[(usize, usize); 4]; 4] = [
+    [(0, 0), (1, 0), (2, 0), (3, 0)],
+    [(0, 1), (1, 1), (2, 1), (3, 1)],
+    [(0, 2), (1, 2), (2, 2), (3, 2)],
+    [(0, 3), (1, 3), (2, 3), (3, 3)],
+];
+const DIAGONALS: [[(usize, usize); 4]; 2] = [
+    [(0, 0), (1, 1), (2, 2), (3, 3)],
+    [(0, 3), (1, 2), (2, 1), (3, 0)],
+];
+
+fn every_line() -> impl Iterator<Item = [(usize, usize); 4]> {
+    ROWS.into_iter().chain(COLUMNS).chain(DIAGONALS)
+}
+
+// Four pieces that only share `attribute`, with `value`
+fn sharing_only(attribute: usize, value: bool) -> [Piece; 4] {
+    let others = [
+        [false; 3],
+        [true; 3],
+        [false, false, true],
+        [true, true, false],
+    ];
+    others.map(|other| {
+        let mut other = other.into_iter();
+        let mut piece = [false; 4];
+        for (index, cell) in piece.iter_mut().enumerate() {
+            *cell = match index == attribute {
+                true => value,
+                false => other.next().expect("3 other attributes"),
+            };
+        }
+        piece
+    })
+}
+
+// Chooses and places each piece in turn, returning the results after each placement
+fn fill<E: Engine>(game: &mut E, pieces: [Piece; 4], line: [(usize, usize); 4]) -> Vec<GameResult> {
+    pieces
+        .into_iter()
+        .zip(line)
+        .map(|(piece, (row, column))| {
+            game.play(Choose(piece)).expect("the piece is available");
+            game.play(Put(Coordinate::new(row, column)))
+                .expect("the cell is empty");
+            game.result()
+        })
+        .collect()
+}
+
+fn check_every_line_wins<E: Engine>() {
+    for line in every_line() {
+        for attribute in 0..4 {
+            for value in [false, true] {
+                let mut game = E::start();
+                let results = fill(&mut game, sharing_only(attribute, value), line);
+                // Player2 places the first piece, so Player1 places the fourth
+                assert_eq!(
+                    results,
+                    [
+                        GameResult::InProgress,
+                        GameResult::InProgress,
+                        GameResult::InProgress,
+                        GameResult::PlayerWon(Player::Player1),
+                    ],
+                    "line {line:?}, attribute {attribute} {value}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn every_line_wins() {
+    on_every_engine!(check_every_line_wins);
+}
+
+fn check_line_without_shared_attribute<E: Engine>() {
+    let pieces = [
+        [false; 4],
+        [true; 4],
+        [false, true, false, true],
+        [true, false, true, false],
+    ];
+    for line in every_line() {
+        let mut game = E::start();
+        let results = fill(&mut game, pieces, line);
+        assert_eq!(results, [GameResult::InProgress; 4], "line {line:?}");
+    }
+}
+
+#[test]
+fn line_without_shared_attribute_does_not_win() {
+    on_every_engine!(check_line_without_shared_attribute);
+}
+
+fn check_last_piece_win<E: Engine>() {
+    let mut game = E::from_notation("d3a0/42ef/7b96/1c.5 8 1 *");
+    game.play(Put(Coordinate::new(3, 2))).unwrap();
+    assert_eq!(game.result(), GameResult::PlayerWon(Player::Player1));
+}
+
+#[test]
+fn last_piece_can_win() {
+    on_every_engine!(check_last_piece_win);
+}
+
+fn check_last_piece_draw<E: Engine>() {
+    let mut game = E::from_notation("2bd./5a04/87f3/61ec 9 1 *");
+    game.play(Put(Coordinate::new(0, 3))).unwrap();
+    assert_eq!(game.result(), GameResult::Draw);
+}
+
+#[test]
+fn full_board_without_line_is_a_draw() {
+    on_every_engine!(check_last_piece_draw);
+}
+
+fn check_out_of_bounds<E: Engine>() {
+    let mut game = E::start();
+    for (row, column) in [(4, 0), (0, 4), (4, 4), (usize::MAX, 0)] {
+        let position = Coordinate::new(row, column);
+        assert_eq!(game.get(position), Err(QuartoError::OutOfBounds(position)));
+    }
+    assert_eq!(game.get(Coordinate::new(3, 3)), Ok(None));
+
+    game.play(Choose([false; 4])).unwrap();
+    let position = Coordinate::new(0, 4);
+    assert_eq!(
+        game.play(Put(position)),
+        Err(QuartoError::OutOfBounds(position))
+    );
+    // The piece can still be placed
+    game.play(Put(Coordinate::new(3, 3))).unwrap();
+    assert_eq!(game.get(Coordinate::new(3, 3)), Ok(Some([false; 4])));
+}
+
+#[test]
+fn positions_outside_the_board_are_rejected() {
+    on_every_engine!(check_out_of_bounds);
+}
+
+fn check_wrong_stage<E: Engine>() {
+    let mut game = E::start();
+    assert_eq!(
+        game.play(Put(Coordinate::new(0, 0))),
+        Err(QuartoError::WrongStage)
+    );
+
+    game.play(Choose([false; 4])).unwrap();
+    assert_eq!(game.play(Choose([true; 4])), Err(QuartoError::WrongStage));
+}
+
+#[test]
+fn actions_of_the_wrong_stage_are_rejected() {
+    on_every_engine!(check_wrong_stage);
+}
+
+fn check_unavailable<E: Engine>() {
+    let mut game = E::start();
+    game.play(Choose([false; 4])).unwrap();
+    game.play(Put(Coordinate::new(0, 0))).unwrap();
+
+    assert_eq!(
+        game.play(Choose([false; 4])),
+        Err(QuartoError::PieceUnavailable([false; 4]))
+    );
+    game.play(Choose([true; 4])).unwrap();
+    assert_eq!(
+        game.play(Put(Coordinate::new(0, 0))),
+        Err(QuartoError::CellOccupied(Coordinate::new(0, 0)))
+    );
+}
+
+#[test]
+fn used_pieces_and_cells_are_rejected() {
+    on_every_engine!(check_unavailable);
+}
+
+fn check_turns<E: Engine>() {
+    let mut game = E::start();
+    assert_eq!(game.player_turn(), Player::Player1);
+    // Choosing gives the turn to the opponent, who places and then chooses
+    game.play(Choose([false; 4])).unwrap();
+    assert_eq!(game.player_turn(), Player::Player2);
+    game.play(Put(Coordinate::new(0, 0))).unwrap();
+    assert_eq!(game.player_turn(), Player::Player2);
+    game.play(Choose([true; 4])).unwrap();
+    assert_eq!(game.player_turn(), Player::Player1);
+}
+
+#[test]
+fn players_take_turns() {
+    on_every_engine!(check_turns);
+}
+
+fn check_game_over<E: Engine>() {
+    let mut game = E::start();
+    fill(&mut game, sharing_only(0, true), ROWS[0]);
+    let result = GameResult::PlayerWon(Player::Player1);
+    assert_eq!(game.result(), result);
+
+    assert_eq!(
+        game.play(Choose([false; 4])),
+        Err(QuartoError::GameOver(result))
+    );
+    assert_eq!(
+        game.play(Put(Coordinate::new(3, 3))),
+        Err(QuartoError::GameOver(result))
+    );
+    assert_eq!(game.get(Coordinate::new(3, 3)), Ok(None));
+}
+
+#[test]
+fn nothing_can_be_played_after_the_game_is_over() {
+    on_every_engine!(check_game_over);
+}
+
+#[test]
+fn finished_game_has_no_legal_actions() {
+    let mut game = Game::from_notation("2bd./5a04/87f3/61ec 9 1 *").unwrap();
+    assert_eq!(game.legal_actions(), [Put(Coordinate::new(0, 3))]);
+    game.put(Coordinate::new(0, 3)).unwrap();
+    assert!(game.legal_actions().is_empty());
+}
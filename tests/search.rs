@@ -0,0 +1,78 @@
+//! Searches of positions too far from the end of the game to be solved.
+
+use quatro_in_rust::alpha_beta::Window;
+use quatro_in_rust::depth_limited::{depth_limited, QuartoEvaluator};
+use quatro_in_rust::iterative_deepening::iterative_deepening;
+use quatro_in_rust::mcts::{Limits, Mcts};
+use quatro_in_rust::{Game, QuartoMinimax};
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// Looking one action further shouldn't change who the estimate favours, whoever moves at the
+// states where the search stops
+#[test]
+fn estimates_keep_their_sign_from_one_depth_to_the_next() {
+    let solver = QuartoMinimax::new(HashMap::new());
+    let evaluator = QuartoEvaluator::default();
+    let mut game = Game::new();
+    for _ in 0..4 {
+        let estimates = (1..=4)
+            .map(|depth| depth_limited(&solver, &evaluator, &game, depth, Window::FULL))
+            .collect::<Vec<_>>();
+        assert!(
+            estimates
+                .iter()
+                .all(|estimate| estimate.signum() == estimates[0].signum()),
+            "{estimates:?} at {}",
+            game.to_notation()
+        );
+        game.play(game.legal_actions()[0]).unwrap();
+    }
+}
+
+// The opening can't be searched to the end in any reasonable time, so the budget is what stops it
+#[test]
+fn iterative_deepening_stops_within_its_budget() {
+    let solver = QuartoMinimax::new(HashMap::new());
+    let mut game = Game::new();
+    game.play(game.legal_actions()[0]).unwrap();
+
+    for budget in [Duration::ZERO, Duration::from_millis(200)] {
+        let start = Instant::now();
+        let (action, _, depth) =
+            iterative_deepening(&solver, &QuartoEvaluator::default(), &game, budget).unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(game.legal_actions().contains(&action));
+        assert!(depth >= 1);
+        // Time out is only noticed between states, and the first search always finishes
+        assert!(
+            elapsed < budget + Duration::from_millis(500),
+            "{elapsed:?} for {budget:?}"
+        );
+    }
+}
+
+// Two players with the same seed make the same moves with the same scores, also when the tree is
+// reused from one move to the next
+#[test]
+fn mcts_with_the_same_seed_is_reproducible() {
+    let limits = Limits {
+        iterations: Some(300),
+        time: None,
+    };
+    let mut game = Game::new();
+    let mut players = [Mcts::new(&game, 7), Mcts::new(&game, 7)];
+
+    for _ in 0..6 {
+        let [first, second] = players.each_mut().map(|player| {
+            player.follow(&game);
+            let (action, score) = player.search(limits).unwrap();
+            (action, score, player.playouts())
+        });
+        assert_eq!(first, second, "{}", game.to_notation());
+        let (action, _, _) = first;
+        game.play(action).unwrap();
+    }
+}
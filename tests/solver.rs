@@ -0,0 +1,145 @@
+//! Values of the solver checked against a plain minimax on positions near the end of the game.
+
+use quatro_in_rust::alpha_beta::{alpha_beta, Window};
+use quatro_in_rust::random::SplitMix64;
+use quatro_in_rust::{Game, GameResult, Minimax, Player, QuartoMinimax, TranspositionTable};
+
+use std::collections::HashMap;
+
+// Positions of games played at random until `pieces_left` pieces are left to choose
+fn endgames(count: usize, pieces_left: usize) -> Vec<Game> {
+    let mut random = SplitMix64::new(0x9e37_79b9_7f4a_7c15);
+
+    let mut games = vec![];
+    while games.len() < count {
+        let mut game = Game::new();
+        while *game.result() == GameResult::InProgress
+            && game.get_pieces_left().len() + 1 > pieces_left
+        {
+            let actions = game.legal_actions();
+            game.play(actions[random.below(actions.len())]).unwrap();
+        }
+        if *game.result() == GameResult::InProgress {
+            games.push(game);
+        }
+    }
+    games
+}
+
+// A win on the n-th piece placed happens after 2n actions, and sooner wins are worth more
+fn plain_minimax(game: &Game) -> i32 {
+    let placed = 16 - game.get_empty_places().len() as i32;
+    match game.result() {
+        GameResult::PlayerWon(Player::Player1) => return 33 - 2 * placed,
+        GameResult::PlayerWon(Player::Player2) => return -(33 - 2 * placed),
+        GameResult::Draw => return 0,
+        GameResult::InProgress => {}
+    }
+
+    let values = game.legal_actions().into_iter().map(|action| {
+        let mut next = game.clone();
+        next.play(action).unwrap();
+        plain_minimax(&next)
+    });
+    match game.player_turn() {
+        Player::Player1 => values.max(),
+        Player::Player2 => values.min(),
+    }
+    .expect("a game in progress has actions")
+}
+
+#[test]
+fn values_match_plain_minimax() {
+    let mut with_symmetries = QuartoMinimax::new(HashMap::new());
+    let mut without_symmetries = QuartoMinimax::new(HashMap::new());
+    without_symmetries.set_use_symmetries(false);
+
+    for game in endgames(40, 5) {
+        let expected = plain_minimax(&game);
+        let notation = game.to_notation();
+        assert_eq!(with_symmetries.value(&game), expected, "{notation}");
+        assert_eq!(without_symmetries.value(&game), expected, "{notation}");
+
+        let mut solver = QuartoMinimax::new(HashMap::new());
+        let (action, value) = solver.best_action(&game).unwrap();
+        assert_eq!(value, expected, "{notation}");
+        let mut next = game.clone();
+        next.play(action).unwrap();
+        assert_eq!(plain_minimax(&next), expected, "{notation}");
+
+        let mut solver = QuartoMinimax::new(HashMap::new());
+        let (_, value) = solver.best_action_parallel(&game, 2).unwrap();
+        assert_eq!(value, expected, "{notation}");
+    }
+}
+
+// Outside the window only a bound is returned, on the side of the window the value is
+#[test]
+fn alpha_beta_matches_plain_minimax() {
+    for game in endgames(20, 5) {
+        let expected = plain_minimax(&game);
+        let notation = game.to_notation();
+        let mut solver = QuartoMinimax::new(HashMap::new());
+        assert_eq!(
+            alpha_beta(&mut solver, &game, Window::FULL),
+            expected,
+            "{notation}"
+        );
+
+        let mut solver = QuartoMinimax::new(HashMap::new());
+        let around = Window::new(expected - 1, expected + 1);
+        assert_eq!(
+            alpha_beta(&mut solver, &game, around),
+            expected,
+            "{notation}"
+        );
+        let below = Window::new(expected - 10, expected - 2);
+        assert!(
+            alpha_beta(&mut solver, &game, below) >= below.beta,
+            "{notation}"
+        );
+        let above = Window::new(expected + 2, expected + 10);
+        assert!(
+            alpha_beta(&mut solver, &game, above) <= above.alpha,
+            "{notation}"
+        );
+    }
+}
+
+#[test]
+fn values_are_kept_in_the_memory() {
+    let games = endgames(10, 4);
+    let mut solver = QuartoMinimax::new(HashMap::new());
+    let values = games
+        .iter()
+        .map(|game| solver.value(game))
+        .collect::<Vec<_>>();
+    assert!(solver.memory_len() > 0);
+
+    // A solver starting from that memory knows the values without searching
+    let solver = QuartoMinimax::new(solver.memory());
+    for (game, value) in games.iter().zip(values) {
+        assert_eq!(
+            solver.known_value(game),
+            Some(value),
+            "{}",
+            game.to_notation()
+        );
+    }
+}
+
+// The threads store into the table itself, which is much smaller than the states searched
+#[test]
+fn parallel_search_stays_within_the_table() {
+    let mut solver = QuartoMinimax::with_table(TranspositionTable::with_memory(4096));
+    let capacity = solver.table().unwrap().capacity();
+    for game in endgames(10, 6) {
+        let (_, value) = solver.best_action_parallel(&game, 3).unwrap();
+        assert_eq!(value, plain_minimax(&game), "{}", game.to_notation());
+        let table = solver.table().expect("the table is given back");
+        assert!(table.len() <= capacity);
+    }
+    // Some positions are won at once, without storing anything
+    assert!(!solver.table().unwrap().is_empty());
+    assert_eq!(solver.memory_len(), 0);
+}
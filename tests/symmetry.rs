@@ -0,0 +1,136 @@
+//! Canonical forms checked against every transformation of the positions, built independently from
+//! the ones of the library.
+
+use quatro_in_rust::random::SplitMix64;
+use quatro_in_rust::{canonical, Game, GameResult};
+
+use std::collections::HashSet;
+
+// For every symmetry, the cell where the content of each cell ends up. They are generated by a
+// rotation, a reflection, swapping the inner and outer rows and columns, and swapping the two
+// inner ones
+fn board_symmetries() -> Vec<[usize; 16]> {
+    let map = |f: fn(usize, usize) -> (usize, usize)| {
+        let mut cells = [0; 16];
+        for (cell, target) in cells.iter_mut().enumerate() {
+            let (row, column) = f(cell / 4, cell % 4);
+            *target = row * 4 + column;
+        }
+        cells
+    };
+    let generators = [
+        map(|row, column| (column, 3 - row)),
+        map(|row, column| (row, 3 - column)),
+        map(|row, column| ([1, 0, 3, 2][row], [1, 0, 3, 2][column])),
+        map(|row, column| ([0, 2, 1, 3][row], [0, 2, 1, 3][column])),
+    ];
+
+    let identity = std::array::from_fn(|cell| cell);
+    let mut symmetries = vec![identity];
+    let mut seen = HashSet::from([identity]);
+    let mut next = 0;
+    while next < symmetries.len() {
+        let symmetry = symmetries[next];
+        for generator in &generators {
+            let composed = std::array::from_fn(|cell| generator[symmetry[cell]]);
+            if seen.insert(composed) {
+                symmetries.push(composed);
+            }
+        }
+        next += 1;
+    }
+    symmetries
+}
+
+fn attribute_permutations() -> Vec<[usize; 4]> {
+    let mut permutations = vec![];
+    for a in 0..4 {
+        for b in 0..4 {
+            for c in 0..4 {
+                for d in 0..4 {
+                    if HashSet::from([a, b, c, d]).len() == 4 {
+                        permutations.push([a, b, c, d]);
+                    }
+                }
+            }
+        }
+    }
+    permutations
+}
+
+// Moves the cells, permutes the attributes of every piece and then inverts the ones in `inverted`
+fn transform(game: &Game, cells: &[usize; 16], permutation: &[usize; 4], inverted: u32) -> Game {
+    let piece = |digit: char| {
+        let index = digit.to_digit(16).unwrap();
+        let permuted = (0..4)
+            .filter(|attribute| index & 1 << attribute != 0)
+            .fold(0, |piece, attribute| piece | 1 << permutation[attribute]);
+        char::from_digit(permuted ^ inverted, 16).unwrap()
+    };
+
+    let notation = game.to_notation();
+    let fields = notation.split(' ').collect::<Vec<_>>();
+    let mut board = ['.'; 16];
+    for (cell, digit) in fields[0].chars().filter(|&c| c != '/').enumerate() {
+        if digit != '.' {
+            board[cells[cell]] = piece(digit);
+        }
+    }
+    let rows = board
+        .chunks(4)
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>();
+    let in_hand = match fields[1] {
+        "-" => '-',
+        digit => piece(digit.chars().next().unwrap()),
+    };
+    format!("{} {in_hand} {} {}", rows.join("/"), fields[2], fields[3])
+        .parse()
+        .unwrap()
+}
+
+// Positions every few actions of a random game, and the end of it
+fn positions() -> Vec<Game> {
+    let mut random = SplitMix64::new(10);
+    let mut game = Game::new();
+    let mut positions = vec![];
+    while *game.result() == GameResult::InProgress {
+        let actions = game.legal_actions();
+        game.play(actions[random.below(actions.len())]).unwrap();
+        if game.history().len().is_multiple_of(8) {
+            positions.push(game.clone());
+        }
+    }
+    positions.push(game);
+    positions
+}
+
+#[test]
+fn canonical_form_is_the_same_for_every_transformation() {
+    let board_symmetries = board_symmetries();
+    assert_eq!(board_symmetries.len(), 32);
+    let attribute_permutations = attribute_permutations();
+    assert_eq!(attribute_permutations.len(), 24);
+
+    for game in positions() {
+        let expected = canonical(&game).to_notation();
+        let mut transformed = HashSet::new();
+        for cells in &board_symmetries {
+            for permutation in &attribute_permutations {
+                for inverted in 0..16 {
+                    let other = transform(&game, cells, permutation, inverted);
+                    assert_eq!(
+                        canonical(&other).to_notation(),
+                        expected,
+                        "{} turned into {}",
+                        game.to_notation(),
+                        other.to_notation()
+                    );
+                    transformed.insert(other.to_notation());
+                }
+            }
+        }
+        // The transformations do move the position around
+        assert!(transformed.len() > 1, "{}", game.to_notation());
+    }
+}
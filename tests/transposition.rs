@@ -0,0 +1,46 @@
+//! Which values a full table keeps.
+
+use quatro_in_rust::TranspositionTable;
+
+// Every key falls in the only bucket of the table
+fn single_bucket() -> TranspositionTable {
+    let table = TranspositionTable::with_memory(0);
+    assert_eq!(table.capacity(), 2);
+    table
+}
+
+#[test]
+fn lowest_priority_or_latest_value_is_replaced() {
+    let mut table = single_bucket();
+    table.insert(1, 10, 5);
+    table.insert(2, 20, 3);
+    assert_eq!(table.len(), 2);
+
+    // The value with the highest priority stays, the latest one is replaced
+    table.insert(3, 30, 1);
+    assert_eq!(table.get(1), Some(10));
+    assert_eq!(table.get(2), None);
+    assert_eq!(table.get(3), Some(30));
+
+    // A higher priority takes the first place, pushing out the latest value
+    table.insert(4, 40, 9);
+    assert_eq!(table.get(4), Some(40));
+    assert_eq!(table.get(1), Some(10));
+    assert_eq!(table.get(3), None);
+    assert_eq!(table.len(), 2);
+}
+
+#[test]
+fn storing_a_key_again_replaces_its_value() {
+    let mut table = single_bucket();
+    table.insert(1, 10, 5);
+    table.insert(2, 20, 3);
+    table.insert(2, 21, 0);
+    assert_eq!(table.get(1), Some(10));
+    assert_eq!(table.get(2), Some(21));
+    assert_eq!(table.len(), 2);
+
+    table.clear();
+    assert!(table.is_empty());
+    assert_eq!(table.get(1), None);
+}